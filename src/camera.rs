@@ -61,7 +61,7 @@ fn camera_follow(
     player_q: Query<&Position, (With<Player>, Changed<Position>)>,
 ) {
     if let Ok(player_pos) = player_q.get_single() {
-        camera.on_player_move(player_pos.tile.to_point());
+        camera.on_player_move(player_pos.to_point());
     }
 }
 
@@ -10,7 +10,12 @@ impl PlanetLocation {
     pub fn new(pos: IVec2) -> Self { Self(pos) }
 
     pub fn to_region_index(&self) -> usize {
-        ((self.x * WORLD_WIDTH as i32) + self.x) as usize
+        ((self.y * WORLD_WIDTH as i32) + self.x) as usize
+    }
+
+    /// Returns true if the location lies within the planet bounds.
+    pub fn is_on_planet(&self) -> bool {
+        (0..WORLD_WIDTH as i32).contains(&self.x) && (0..WORLD_HEIGHT as i32).contains(&self.y)
     }
 
     pub fn to_world(&self) -> IVec2 {
@@ -1,7 +1,7 @@
 use crate::prelude::*;
 
 /// Represents a location in the world
-#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
     pub region: PlanetLocation,
     pub chunk_min: ChunkLocation,
@@ -15,13 +15,29 @@ impl Position {
     }
 
     pub fn with_tile_coords<N: Into<i32>>(region: PlanetLocation, x: N, y: N) -> Self {
+        let mut pos = Self::new(region, RegionTileLocation::new(x, y));
+        pos.chunk_min = pos.chunk_location();
+        pos
+    }
+
+    /// The position of a world-space tile, wrapped around the east/west edge of the planet.
+    pub fn from_world(world: IVec2) -> Self {
+        let world = IVec2::new(world.x.rem_euclid(PLANET_TILE_WIDTH), world.y);
+        let region = ChunkLocation::from(world).to_planet_location();
+        let tile = world - region.to_world();
+
         Self {
             region,
-            chunk_min: region.to_world().into(),
-            tile: RegionTileLocation::new(x, y),
+            chunk_min: ChunkLocation::from(!IVec2::splat(CHUNK_SIZE_I32 - 1) & world),
+            tile: RegionTileLocation::new(tile.x, tile.y),
         }
     }
 
+    /// The chunk this position lies in
+    pub fn chunk_location(&self) -> ChunkLocation {
+        ChunkLocation::from(!IVec2::splat(CHUNK_SIZE_I32 - 1) & self.to_world())
+    }
+
     /// Convert to a region tile index
     pub fn to_tile_index(&self) -> usize { self.tile.to_tile_index() }
 
@@ -33,28 +49,10 @@ impl Position {
         Point::new(world_pt.x, world_pt.y)
     }
 
-    /// Apply a tile offset and recalculate IDs as needed.
+    /// Apply a tile offset and recalculate IDs as needed, moving into the neighboring
+    /// region when the offset crosses a region edge.
     /// Returns a new position.
     pub fn offset<N: Into<i32>>(&self, x: N, y: N) -> Self {
-        let mut new_pos = (self.tile.x + x.into(), self.tile.y + y.into());
-        let mut region = self.region;
-        while new_pos.0 < 0 {
-            region.x -= 1;
-            new_pos.0 += REGION_WIDTH as i32;
-        }
-        while new_pos.0 > REGION_WIDTH as i32 - 1 {
-            region.x += 1;
-            new_pos.0 -= REGION_WIDTH as i32;
-        }
-        while new_pos.1 < 0 {
-            region.y -= 1;
-            new_pos.1 += REGION_WIDTH as i32;
-        }
-        while new_pos.1 > REGION_HEIGHT as i32 - 1 {
-            region.y += 1;
-            new_pos.1 -= REGION_WIDTH as i32;
-        }
-
-        Self::with_tile_coords(region, new_pos.0, new_pos.1)
+        Self::from_world(self.to_world() + IVec2::new(x.into(), y.into()))
     }
 }
@@ -2,3 +2,8 @@ use bevy::prelude::Component;
 
 #[derive(Component)]
 pub struct Player;
+
+/// Entities saved with the game. The player is saved in the game save, everything else in
+/// the save of the chunk it stands in when that chunk unloads.
+#[derive(Component)]
+pub struct Persistent;
@@ -1,7 +1,7 @@
 use crate::prelude::*;
 
 pub fn fov(
-    loaded_chunks: Res<ChunkEntities>,
+    chunks: Res<ChunkMap<TileType, ChunkShape>>,
     mut fov_q: Query<(&Position, &mut FieldOfView)>,
 ) {
     let regions = REGIONS.read();
@@ -9,14 +9,13 @@ pub fn fov(
         fov.is_dirty = false;
 
         let pt = pos.tile.to_point();
-        let chunk_key = pos.chunk_min;
 
         let region = regions.get_region(pos.region.to_region_index()).unwrap();
 
-        if loaded_chunks.entity(chunk_key).is_some() {
+        if chunks.get_tile(pos.to_world()).is_some() {
             fov.visible_tiles = field_of_view_set(Point::new(pt.x, pt.y), fov.radius, region);
         } else {
-            println!("FOV: No chunk entity found for {:?}", chunk_key);
+            println!("FOV: No chunk loaded for {:?}", pos.chunk_min);
         }
     }
 }
@@ -1,23 +1,27 @@
 use crate::prelude::*;
 
 pub fn movement(
+    chunks: Res<ChunkMap<TileType, ChunkShape>>,
     mut chunk_pos: ResMut<CurrentLocalPlayerChunk>,
     mut move_events: ResMut<Events<WantsToMove>>,
     mut pos_q: Query<(&mut Position, Option<&Player>)>,
 ) {
     for WantsToMove(entity, destination) in move_events.drain() {
         if let Ok((mut pos, player)) = pos_q.get_mut(entity) {
-            pos.tile = RegionTileLocation::new(destination.x, destination.y);
-
-            let world_pos: IVec2 = pos.tile.to_world();
-            let nearest_chunk_origin = !IVec2::splat((CHUNK_SIZE - 1) as i32) & world_pos;
-            if pos.chunk_min != nearest_chunk_origin.into() {
-                pos.chunk_min = nearest_chunk_origin.into();
+            // Only walk onto loaded, passable tiles
+            match chunks.get_tile(IVec2::new(destination.x, destination.y)) {
+                None | Some(TileType::Wall) => continue,
+                _ => {}
             }
 
+            // Crossing a region edge moves the position into the neighboring region, and the
+            // planet wraps around on the east/west edge
+            let world_pos = pos.to_world();
+            *pos = pos.offset(destination.x - world_pos.x, destination.y - world_pos.y);
+
             if player.is_some() {
-                chunk_pos.world_pos = world_pos;
-                chunk_pos.chunk_min = nearest_chunk_origin;
+                chunk_pos.world_pos = pos.to_world();
+                chunk_pos.chunk_min = pos.chunk_min.as_ivec2();
             }
         }
     }
@@ -24,7 +24,7 @@ pub fn player_input(
 
         // move to new position
         if delta.x != 0 || delta.y != 0 {
-            let destination = pos.tile.to_point() + delta;
+            let destination = pos.to_point() + delta;
             move_events.send(WantsToMove(player_entity, destination));
         }
 
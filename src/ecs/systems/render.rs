@@ -1,7 +1,7 @@
 use crate::prelude::*;
 
 pub fn render_state(
-    chunks: Query<&Chunk>,
+    chunks: Res<ChunkMap<TileType, ChunkShape>>,
     camera: Res<CameraView>,
     ctx: Res<BracketContext>,
     renderables: Query<(&Glyph, &Position)>,
@@ -11,10 +11,11 @@ pub fn render_state(
     batch.target(LAYER_ZERO);
     batch.cls();
 
-    chunks.iter().for_each(|chunk| {
-        chunk.tiles.iter().enumerate().for_each(|(idx, tile)| {
+    chunks.iter().for_each(|(chunk_min, buffer)| {
+        let chunk_origin = ChunkLocation::from(chunk_min).nearest_to(camera.player_pos.x);
+        buffer.iter().enumerate().for_each(|(idx, tile)| {
             let pt = Point::new(idx % CHUNK_SIZE, idx / CHUNK_SIZE)
-                + Point { x: chunk.location.x as i32, y: chunk.location.y as i32 };
+                + Point::new(chunk_origin.x, chunk_origin.y);
 
             if camera.viewport.point_in_rect(pt) {
                 let screen_pt = camera.world_to_screen(pt);
@@ -33,6 +34,7 @@ pub fn render_state(
                         PlantType::Daisy => ('d', YELLOW),
                         PlantType::Heather => ('h', PURPLE),
                     },
+                    TileType::Ramp => ('▲', WHITE),
                     // TileType::Empty => (' ', BLACK),
                 };
 
@@ -44,7 +46,7 @@ pub fn render_state(
     let mut entities = renderables.iter().collect::<Vec<_>>();
     entities.sort_by(|&a, &b| b.0.render_order.cmp(&a.0.render_order));
     for (glyph, pos) in entities {
-        let screen_pt = camera.world_to_screen(pos.tile.to_point());
+        let screen_pt = camera.world_to_screen(pos.to_point());
         batch.set(screen_pt, glyph.color, glyph.glyph);
     }
 
@@ -1,12 +1,17 @@
 use super::*;
 
+/// Loads the raw files. The game can't go on without them, so a failure is reported and
+/// leaves the loading screen up.
 pub fn load_raws(
     commands: &mut Commands,
     res: &mut ResMut<LoadingResource>,
+    errors: &mut ResMut<ErrorDialog>,
     ui: &mut egui::Ui,
 ) {
     res.cycle += 1;
     ui.label("Loading Raw Files");
-    crate::raws::load_raws();
-    commands.insert_resource(NextState(GameState::PlanetGen));
+    match crate::raws::load_raws() {
+        Ok(_) => commands.insert_resource(NextState(GameState::PlanetGen)),
+        Err(err) => errors.report("Failed to load raw files", &err),
+    }
 }
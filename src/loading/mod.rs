@@ -15,6 +15,7 @@ pub fn resume_loading_screen(mut commands: Commands) {
 pub fn loading_screen(
     mut commands: Commands,
     mut res: ResMut<LoadingResource>,
+    mut errors: ResMut<ErrorDialog>,
     mut egui_context: ResMut<EguiContext>,
 ) {
     egui::Window::new("Loading - Please Wait")
@@ -23,7 +24,7 @@ pub fn loading_screen(
         .fixed_pos(egui::Pos2::new(500.0, 200.0))
         .show(egui_context.ctx_mut(), |ui| match res.cycle {
             0..=2 => res.cycle += 1,
-            3 => loaders::load_raws(&mut commands, &mut res, ui),
+            3 => loaders::load_raws(&mut commands, &mut res, &mut errors, ui),
             _ => {}
         });
 }
@@ -55,6 +55,30 @@ pub use prelude::*;
 fn main() {
     // Setup folders for saving
     setup_io_access().expect("Failed to setup IO access");
+    match migrate_legacy_save() {
+        Ok(Some(slot)) => println!("Moved the existing world to save slot {}", slot.name()),
+        Ok(None) => {}
+        Err(err) => {
+            println!("Failed to move the existing world to a save slot: {}", err.report())
+        }
+    }
+    for slot in list_save_slots() {
+        match migrate_chunk_files(&slot) {
+            Ok(0) => {}
+            Ok(migrated) => println!("Packed {migrated} chunk files into region files"),
+            Err(err) => println!("Failed to migrate chunk files: {}", err.report()),
+        }
+    }
+
+    // Exporting and importing worlds happens without opening the game
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(result) = run_save_command(&args) {
+        if let Err(err) = result {
+            println!("{}", err.report());
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let mut app = App::new();
 
@@ -87,10 +111,12 @@ fn main() {
         .add_plugins(UIPlugins)
         .add_plugin(EcsPlugin)
         .add_plugin(CameraPlugin)
-        .add_plugin(ChunkingPlugin);
+        .add_plugin(ChunkingPlugin)
+        .add_plugin(RegionStreamingPlugin);
 
     app.init_resource::<UiResources>()
         .insert_resource(ChunkMap::<TileType, ChunkShape>::new(ChunkShape {}))
+        .insert_resource(ChunkMap::<u32, ChunkShape>::new(ChunkShape {}))
         .add_startup_system(setup);
 
     app.insert_resource(PlanetBuilder::new())
@@ -98,14 +124,6 @@ fn main() {
         .add_system(wait_for_planet_spawn.run_in_state(GameState::PlanetGenWait))
         .add_system(wait_for_region_spawn.run_in_state(GameState::RegionGenWait));
 
-    // app.add_system_set(
-    //     ConditionSet::new()
-    //         .run_in_state(GameState::RegionGen)
-    //         // .with_system(load_regions)
-    //         .with_system(region_tile_applicator_system)
-    //         .into(),
-    // );
-
     app.run();
 }
 
@@ -120,34 +138,50 @@ pub fn spawn_planet(mut commands: Commands, rng: Res<RandomNumbers>) {
     let pb = PlanetBuilder::new();
     pb.generate(&seed.to_string(), worldgen_lacunarity);
     commands.insert_resource(pb);
+
+    // Chunks of the generated world are saved in a slot of their own
+    commands.insert_resource(SaveSlot::unique(&seed.to_string()));
     commands.insert_resource(NextState(GameState::PlanetGenWait));
 }
 
-pub fn wait_for_planet_spawn(mut commands: Commands, pb: Res<PlanetBuilder>) {
+pub fn wait_for_planet_spawn(
+    mut commands: Commands,
+    pb: Res<PlanetBuilder>,
+    slot: Res<SaveSlot>,
+) {
     if pb.is_done() {
         let planet = pb.get_planet().unwrap();
         let crash_location = PlanetLocation::new((0, 0).into());
-        let tile_loc = crash_location.to_world();
-        let pos = Position::with_tile_coords(crash_location, tile_loc.x, tile_loc.y);
+        let pos = Position::with_tile_coords(crash_location, 0, 0);
+        let tile_loc = pos.to_world();
 
         commands
             .spawn()
             .insert(Player)
+            .insert(Persistent)
             .insert(pos)
             .insert(Glyph::new(
                 to_cp437('@'),
                 ColorPair::new(WHITE, BLACK),
                 RenderOrder::Actor,
             ))
-            .insert(FieldOfView::new(8));
+            .insert(FieldOfView::new(8))
+            .insert(ChunkViewer::default());
 
-        commands.insert_resource(CurrentLocalPlayerChunk::new(
-            crash_location.to_world(),
-            tile_loc,
-        ));
+        commands
+            .insert_resource(CurrentLocalPlayerChunk::new(pos.chunk_min.as_ivec2(), tile_loc));
         commands.insert_resource(CameraView::new(Point::new(tile_loc.x, tile_loc.y)));
 
-        let mut rb = RegionBuilder::new(planet, crash_location);
+        // Save the world into its slot, so it can be resumed like one created from the menu
+        let mut metadata =
+            WorldMetadata::new(&planet.noise_seed.to_string(), planet.lacunarity);
+        metadata.embark = Some(crash_location);
+        save_planet(&slot, planet.clone());
+        if let Err(err) = save_metadata(&slot, &metadata) {
+            println!("Error saving world metadata: {}", err.report());
+        }
+
+        let mut rb = RegionBuilder::new(planet, crash_location, slot.clone());
         rb.generate();
 
         commands.insert_resource(rb);
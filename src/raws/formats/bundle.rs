@@ -1,4 +1,5 @@
 use super::*;
+use crate::saveload::IOError;
 use ron::de::from_reader;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
@@ -11,18 +12,12 @@ pub struct RawBundle {
 }
 
 impl RawBundle {
-    pub fn load(filename: &str) -> Self {
+    pub fn load(filename: &str) -> Result<Self, IOError> {
         println!("loading raw bundle: {}", filename);
-        let f = File::open(filename).expect("Failed opening file");
-
-        let bundle: RawBundle = match from_reader(f) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Failed to load bundle list: {}: {:?}", filename, e);
-                std::process::exit(1);
-            }
-        };
-        bundle
+        File::open(filename)
+            .map_err(IOError::FailedToOpenFile)
+            .and_then(|f| from_reader(f).map_err(IOError::FailedToParseRaws))
+            .map_err(|err| err.in_file(filename))
     }
 
     pub fn merge(&self, raws: &mut crate::raws::Raws) {
@@ -1,3 +1,4 @@
+use crate::saveload::IOError;
 use lazy_static::lazy_static;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
@@ -31,31 +32,36 @@ impl Raws {
         }
     }
 
-    fn load_index(&self) -> Vec<String> {
+    fn load_index(&self) -> Result<Vec<String>, IOError> {
         use std::fs::File;
         use std::io::{BufRead, BufReader};
 
-        let file = File::open("raws/index.txt").unwrap();
+        const INDEX: &str = "raws/index.txt";
+        let file =
+            File::open(INDEX).map_err(|err| IOError::FailedToOpenFile(err).in_file(INDEX))?;
         let reader = BufReader::new(file);
-        reader
+        let lines = reader
             .lines()
-            .map(|l| l.unwrap())
-            .filter(|l| !l.is_empty() && !l.starts_with("# "))
-            .collect()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| IOError::FailedToReadFile(err).in_file(INDEX))?;
+
+        Ok(lines.into_iter().filter(|l| !l.is_empty() && !l.starts_with("# ")).collect())
     }
 
-    fn load(&mut self) {
+    fn load(&mut self) -> Result<(), IOError> {
         self.names = load_names();
 
-        let bundles = self.load_index();
-        bundles.iter().for_each(|bf| {
-            let bundle = RawBundle::load(bf);
+        for bf in self.load_index()? {
+            let bundle = RawBundle::load(&bf)?;
             bundle.merge(self);
-        });
+        }
+
+        Ok(())
     }
 }
 
-pub fn load_raws() {
-    RAWS.write().load();
+pub fn load_raws() -> Result<(), IOError> {
+    RAWS.write().load()?;
     strata::verify_strata();
+    Ok(())
 }
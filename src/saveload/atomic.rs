@@ -0,0 +1,92 @@
+use crate::prelude::*;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+lazy_static! {
+    static ref SYNC_MODE: RwLock<SyncMode> = RwLock::new(SyncMode::default());
+}
+
+/// How far saves are flushed to disk before a write counts as done.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Leave flushing to the OS. Fastest, but a power loss can lose recent saves.
+    Never,
+    /// Sync the file contents before it replaces the previous save.
+    #[default]
+    File,
+    /// Also sync the directory, so the rename itself survives a power loss.
+    FileAndDirectory,
+}
+
+pub fn sync_mode() -> SyncMode { *SYNC_MODE.read() }
+
+pub fn set_sync_mode(mode: SyncMode) { *SYNC_MODE.write() = mode; }
+
+/// The previous save of `file_path`, kept around in case the latest one is corrupt.
+pub fn backup_path(file_path: &str) -> String { format!("{file_path}.bak") }
+
+/// Writes `bytes` to a temporary file and renames it over `file_path`, so a crash leaves
+/// either the old or the new file but never a truncated one.
+pub fn write_atomic(file_path: &Path, bytes: &[u8]) -> Result<(), IOError> {
+    let sync = sync_mode();
+    let mut temp_path = file_path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+
+    let mut file = File::create(&temp_path).map_err(IOError::FailedToWriteFile)?;
+    file.write_all(bytes).map_err(IOError::FailedToWriteFile)?;
+    if sync != SyncMode::Never {
+        file.sync_all().map_err(IOError::FailedToWriteFile)?;
+    }
+    drop(file);
+
+    fs::rename(&temp_path, file_path).map_err(IOError::FailedToWriteFile)?;
+    sync_parent_dir(file_path);
+    Ok(())
+}
+
+/// Syncs the directory holding `file_path` after a rename, in [`SyncMode::FileAndDirectory`].
+pub fn sync_parent_dir(file_path: &Path) {
+    if sync_mode() == SyncMode::FileAndDirectory {
+        // Directories can't be opened on every platform, the rename is done either way
+        if let Some(dir) = file_path.parent() {
+            let _ = File::open(dir).and_then(|dir| dir.sync_all());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_corrupt_save_falls_back_to_backup() {
+        let key = "test/atomic_save_backup.chunk";
+
+        let region = PlanetLocation::new(IVec2::ZERO);
+        save_data(key.to_string(), Chunk::new(region, ChunkLocation::new(0, 0))).unwrap();
+        save_data(key.to_string(), Chunk::new(region, ChunkLocation::new(32, 0))).unwrap();
+        assert_eq!(load_data::<Chunk>(key.to_string()).unwrap().location.x, 32);
+
+        // Truncated, as a crash mid-write would have left it without the atomic write
+        let storage = save_storage();
+        let bytes = storage.read(key).unwrap();
+        storage.write(key, &bytes[..bytes.len() / 2]).unwrap();
+        assert_eq!(load_data::<Chunk>(key.to_string()).unwrap().location.x, 0);
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let path = std::env::temp_dir().join("atomic_save_write.dat");
+        write_atomic(&path, &[1, 2]).unwrap();
+        write_atomic(&path, &[3]).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), vec![3]);
+        assert!(!std::env::temp_dir().join("atomic_save_write.dat.tmp").exists());
+        let _ = fs::remove_file(&path);
+    }
+}
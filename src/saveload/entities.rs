@@ -0,0 +1,108 @@
+use crate::prelude::*;
+use bracket_bevy::FontCharType;
+
+/// The saved components of a [`Persistent`] entity, as read by [`SavedEntity::capture`].
+pub type PersistentComponents<'a> = (
+    &'a Position,
+    Option<&'a Glyph>,
+    Option<&'a FieldOfView>,
+    Option<&'a ChunkViewer>,
+    Option<&'a Player>,
+);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGlyph {
+    pub glyph: FontCharType,
+    pub fg: [f32; 4],
+    pub bg: [f32; 4],
+    pub render_order: RenderOrder,
+}
+
+impl SavedGlyph {
+    pub fn new(glyph: &Glyph) -> Self {
+        let (fg, bg) = (glyph.color.fg, glyph.color.bg);
+        Self {
+            glyph: glyph.glyph,
+            fg: [fg.r, fg.g, fg.b, fg.a],
+            bg: [bg.r, bg.g, bg.b, bg.a],
+            render_order: glyph.render_order,
+        }
+    }
+
+    pub fn to_glyph(&self) -> Glyph {
+        let [fr, fg, fb, fa] = self.fg;
+        let [br, bg, bb, ba] = self.bg;
+        Glyph::new(
+            self.glyph,
+            ColorPair::new(RGBA::from_f32(fr, fg, fb, fa), RGBA::from_f32(br, bg, bb, ba)),
+            self.render_order,
+        )
+    }
+}
+
+/// A [`Persistent`] entity in a save. Components the entity doesn't have are left empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedEntity {
+    pub position: Position,
+    pub glyph: Option<SavedGlyph>,
+    pub fov_radius: Option<i32>,
+    pub viewer: Option<ChunkViewer>,
+    pub player: bool,
+}
+
+impl SavedEntity {
+    pub fn capture((position, glyph, fov, viewer, player): PersistentComponents) -> Self {
+        Self {
+            position: *position,
+            glyph: glyph.map(SavedGlyph::new),
+            fov_radius: fov.map(|fov| fov.radius),
+            viewer: viewer.copied(),
+            player: player.is_some(),
+        }
+    }
+
+    /// Spawns the entity back into the world.
+    pub fn spawn(&self, commands: &mut Commands) -> Entity {
+        let mut entity = commands.spawn();
+        entity.insert(self.position).insert(Persistent);
+
+        if let Some(glyph) = &self.glyph {
+            entity.insert(glyph.to_glyph());
+        }
+        if let Some(radius) = self.fov_radius {
+            entity.insert(FieldOfView::new(radius));
+        }
+        if let Some(viewer) = self.viewer {
+            entity.insert(viewer);
+        }
+        if self.player {
+            entity.insert(Player);
+        }
+
+        entity.id()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Game Save
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// The state of a game in progress, resumed instead of embarking again. Entities other than
+/// the player are saved with their chunk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSave {
+    pub player: SavedEntity,
+}
+
+impl SavePayload for GameSave {
+    const PAYLOAD: PayloadType = PayloadType::Game;
+    const VERSION: u32 = 2;
+}
+
+pub fn does_game_file_exist(slot: &SaveSlot) -> bool { check_save_exists(&slot.game_path()) }
+
+pub fn load_game(slot: &SaveSlot) -> Result<GameSave, IOError> { load_data(slot.game_path()) }
+
+pub fn save_game(slot: &SaveSlot, game: &GameSave) -> Result<(), IOError> {
+    save_data(slot.game_path(), game.clone())
+}
@@ -0,0 +1,174 @@
+use crate::prelude::*;
+use miniz_oxide::inflate::TINFLStatus;
+use std::{error::Error, fmt, io};
+
+/// Everything that can go wrong reading or writing saves and raw files. Errors wrap the
+/// error that caused them, and are wrapped in turn with the file, chunk or world they
+/// happened in, see [`IOError::in_file`].
+#[derive(Debug)]
+pub enum IOError {
+    // Load
+    FailedToOpenFile(io::Error),
+    FailedToReadFile(io::Error),
+    FailedToDecompressFile(TINFLStatus),
+    FailedToDeserialize(bincode::Error),
+    FailedToParseRaws(ron::error::SpannedError),
+    /// An exported RON or JSON file couldn't be parsed.
+    FailedToParseText(Box<dyn Error + Send + Sync>),
+
+    // Save
+    FailedToCreateDir(io::Error),
+    FailedToWriteFile(io::Error),
+    FailedToDeleteFile(io::Error),
+    FailedToSerialize(bincode::Error),
+    FailedToFormatText(Box<dyn Error + Send + Sync>),
+    SaveFileDoesNotExist,
+    /// The data is cut short or points outside of itself.
+    SaveFileCorrupted,
+
+    // Versioning
+    UnknownPayloadType(u32),
+    WrongPayloadType {
+        expected: PayloadType,
+        found: PayloadType,
+    },
+    ChecksumMismatch,
+    /// The save was written by a newer version of the game.
+    SaveFileTooNew {
+        version: u32,
+        supported: u32,
+    },
+    MissingMigration {
+        payload: PayloadType,
+        version: u32,
+    },
+
+    // Context
+    InFile {
+        path: String,
+        source: Box<IOError>,
+    },
+    InChunk {
+        chunk: ChunkLocation,
+        source: Box<IOError>,
+    },
+    InWorld {
+        world: String,
+        source: Box<IOError>,
+    },
+}
+
+impl IOError {
+    pub fn in_file(self, path: impl Into<String>) -> Self {
+        Self::InFile { path: path.into(), source: Box::new(self) }
+    }
+
+    pub fn in_chunk(self, chunk: ChunkLocation) -> Self {
+        Self::InChunk { chunk, source: Box::new(self) }
+    }
+
+    pub fn in_world(self, slot: &SaveSlot) -> Self {
+        Self::InWorld { world: slot.name().to_string(), source: Box::new(self) }
+    }
+
+    /// The error at the bottom of the context, which says what actually went wrong.
+    pub fn root(&self) -> &IOError {
+        match self {
+            Self::InFile { source, .. }
+            | Self::InChunk { source, .. }
+            | Self::InWorld { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// The error followed by every error that caused it, one per line.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = self.source();
+        while let Some(err) = source {
+            report.push_str(&format!("\n  caused by: {err}"));
+            source = err.source();
+        }
+
+        report
+    }
+}
+
+impl fmt::Display for IOError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToOpenFile(_) => write!(f, "failed to open file"),
+            Self::FailedToReadFile(_) => write!(f, "failed to read file"),
+            Self::FailedToDecompressFile(status) => {
+                write!(f, "failed to decompress file ({status:?})")
+            }
+            Self::FailedToDeserialize(_) => write!(f, "failed to deserialize data"),
+            Self::FailedToParseRaws(_) => write!(f, "failed to parse raw file"),
+            Self::FailedToParseText(_) => write!(f, "failed to parse exported file"),
+            Self::FailedToCreateDir(_) => write!(f, "failed to create directory"),
+            Self::FailedToWriteFile(_) => write!(f, "failed to write file"),
+            Self::FailedToDeleteFile(_) => write!(f, "failed to delete file"),
+            Self::FailedToSerialize(_) => write!(f, "failed to serialize data"),
+            Self::FailedToFormatText(_) => write!(f, "failed to export data"),
+            Self::SaveFileDoesNotExist => write!(f, "save file does not exist"),
+            Self::SaveFileCorrupted => write!(f, "save file is corrupted"),
+            Self::UnknownPayloadType(payload) => write!(f, "unknown payload type {payload}"),
+            Self::WrongPayloadType { expected, found } => {
+                write!(f, "expected a {expected:?} save, found a {found:?} save")
+            }
+            Self::ChecksumMismatch => write!(f, "save file checksum does not match"),
+            Self::SaveFileTooNew { version, supported } => write!(
+                f,
+                "save file version {version} is newer than the supported version {supported}"
+            ),
+            Self::MissingMigration { payload, version } => {
+                write!(f, "no migration from version {version} of {payload:?} saves")
+            }
+            Self::InFile { path, .. } => write!(f, "error in file {path}"),
+            Self::InChunk { chunk, .. } => {
+                write!(f, "error in chunk ({}, {})", chunk.x, chunk.y)
+            }
+            Self::InWorld { world, .. } => write!(f, "error in world {world}"),
+        }
+    }
+}
+
+impl Error for IOError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::FailedToOpenFile(err)
+            | Self::FailedToReadFile(err)
+            | Self::FailedToCreateDir(err)
+            | Self::FailedToWriteFile(err)
+            | Self::FailedToDeleteFile(err) => Some(err),
+            Self::FailedToDeserialize(err) | Self::FailedToSerialize(err) => Some(err),
+            Self::FailedToParseRaws(err) => Some(err),
+            Self::FailedToParseText(err) | Self::FailedToFormatText(err) => Some(err.as_ref()),
+            Self::InFile { source, .. }
+            | Self::InChunk { source, .. }
+            | Self::InWorld { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_context() {
+        let chunk = ChunkLocation::new(32, 64);
+        let err = IOError::ChecksumMismatch
+            .in_file("worlds/a/chunks/0_0.region")
+            .in_chunk(chunk)
+            .in_world(&SaveSlot::new("a"));
+
+        assert!(matches!(err.root(), IOError::ChecksumMismatch));
+        assert_eq!(
+            err.report(),
+            "error in world a\n  caused by: error in chunk (32, 64)\n  caused by: error in \
+             file worlds/a/chunks/0_0.region\n  caused by: save file checksum does not match"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use crate::prelude::*;
+use serde::de::DeserializeOwned;
+use std::{fs, path::Path};
+
+const PLANET_EXPORT: &str = "planet";
+const METADATA_EXPORT: &str = "metadata";
+const CHUNK_EXPORT_DIR: &str = "chunks";
+
+/// Human readable format of exported saves, picked from the file extension on import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    Ron,
+    Json,
+}
+
+impl TextFormat {
+    pub const ALL: [TextFormat; 2] = [TextFormat::Ron, TextFormat::Json];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.extension() == name.to_lowercase())
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ron => "ron",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn to_text<T: Serialize>(&self, value: &T) -> Result<String, IOError> {
+        match self {
+            Self::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map_err(|err| IOError::FailedToFormatText(Box::new(err))),
+            Self::Json => serde_json::to_string_pretty(value)
+                .map_err(|err| IOError::FailedToFormatText(Box::new(err))),
+        }
+    }
+
+    pub fn from_text<T: DeserializeOwned>(&self, text: &str) -> Result<T, IOError> {
+        match self {
+            Self::Ron => {
+                ron::from_str(text).map_err(|err| IOError::FailedToParseText(Box::new(err)))
+            }
+            Self::Json => serde_json::from_str(text)
+                .map_err(|err| IOError::FailedToParseText(Box::new(err))),
+        }
+    }
+}
+
+fn write_text<T: Serialize>(
+    path: &Path,
+    value: &T,
+    format: TextFormat,
+) -> Result<(), IOError> {
+    format
+        .to_text(value)
+        .and_then(|text| fs::write(path, text).map_err(IOError::FailedToWriteFile))
+        .map_err(|err| err.in_file(path.to_string_lossy()))
+}
+
+fn read_text<T: DeserializeOwned>(path: &Path) -> Result<T, IOError> {
+    let format = path
+        .extension()
+        .and_then(|ext| TextFormat::from_name(&ext.to_string_lossy()))
+        .ok_or_else(|| IOError::FailedToParseText("not a .ron or .json file".into()));
+
+    format
+        .and_then(|format| {
+            let text = fs::read_to_string(path).map_err(IOError::FailedToReadFile)?;
+            format.from_text(&text)
+        })
+        .map_err(|err| err.in_file(path.to_string_lossy()))
+}
+
+/// Finds `{name}.ron` or `{name}.json` in `dir`.
+fn find_text(dir: &Path, name: &str) -> Option<std::path::PathBuf> {
+    TextFormat::ALL
+        .iter()
+        .map(|format| dir.join(format!("{name}.{}", format.extension())))
+        .find(|path| path.is_file())
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Export
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Writes the chunks saved in the slot to `{dir}/chunks/{x}_{y}.{ext}`. Returns the number
+/// of chunks written, chunks that were never saved are skipped.
+pub fn export_chunks(
+    slot: &SaveSlot,
+    chunk_ids: &[ChunkLocation],
+    dir: &Path,
+    format: TextFormat,
+) -> Result<usize, IOError> {
+    let chunk_dir = dir.join(CHUNK_EXPORT_DIR);
+    fs::create_dir_all(&chunk_dir).map_err(IOError::FailedToCreateDir)?;
+
+    let mut exported = 0;
+    for chunk_id in chunk_ids.iter().copied() {
+        let data = match read_chunk_data(slot, chunk_id)? {
+            Some(data) => data,
+            None => continue,
+        };
+        let chunk = decode_data::<Chunk>(&data).map_err(|err| err.in_chunk(chunk_id))?;
+
+        let file_name = format!("{}_{}.{}", chunk_id.x, chunk_id.y, format.extension());
+        write_text(&chunk_dir.join(file_name), &chunk, format)?;
+        exported += 1;
+    }
+
+    Ok(exported)
+}
+
+/// Writes the planet, the metadata and every chunk saved in the slot to `dir`, in a layout
+/// [`import_world`] reads back.
+pub fn export_world(
+    slot: &SaveSlot,
+    dir: &Path,
+    format: TextFormat,
+) -> Result<usize, IOError> {
+    fs::create_dir_all(dir).map_err(IOError::FailedToCreateDir)?;
+
+    let ext = format.extension();
+    write_text(&dir.join(format!("{PLANET_EXPORT}.{ext}")), &load_planet(slot)?, format)?;
+    if let Ok(metadata) = load_metadata(slot) {
+        write_text(&dir.join(format!("{METADATA_EXPORT}.{ext}")), &metadata, format)?;
+    }
+
+    let mut chunk_ids = saved_chunks(slot);
+    chunk_ids.sort_by_key(|chunk| (chunk.y, chunk.x));
+    export_chunks(slot, &chunk_ids, dir, format).map_err(|err| err.in_world(slot))
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Import
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Rebuilds a binary save in the slot from a world written by [`export_world`], which may
+/// mix RON and JSON files. Returns the number of chunks imported.
+pub fn import_world(dir: &Path, slot: &SaveSlot) -> Result<usize, IOError> {
+    let planet_path = find_text(dir, PLANET_EXPORT).ok_or_else(|| {
+        IOError::SaveFileDoesNotExist.in_file(dir.join(PLANET_EXPORT).to_string_lossy())
+    })?;
+    let planet = read_text::<Planet>(&planet_path)?;
+
+    let metadata = match find_text(dir, METADATA_EXPORT) {
+        Some(path) => read_text(&path)?,
+        None => WorldMetadata::new(&planet.noise_seed.to_string(), planet.lacunarity),
+    };
+    save_data(slot.planet_path(), planet)?;
+    save_metadata(slot, &metadata)?;
+
+    let entries = match fs::read_dir(dir.join(CHUNK_EXPORT_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+
+    let mut imported = 0;
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let ext = path.extension().map(|ext| ext.to_string_lossy().to_string());
+        if ext.and_then(|ext| TextFormat::from_name(&ext)).is_none() {
+            continue;
+        }
+
+        let chunk = read_text::<Chunk>(&path)?;
+        write_chunk_data(slot, chunk.location, &encode_data(&chunk)?)?;
+        imported += 1;
+    }
+
+    Ok(imported)
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Commands
+//////////////////////////////////////////////////////////////////////////////////////////
+
+const SAVE_COMMAND_USAGE: &str = "usage:
+    export <world> <dir> [ron|json]
+    import <dir> <world>";
+
+/// Runs the export or import command given on the command line, if any.
+pub fn run_save_command(args: &[String]) -> Option<Result<(), IOError>> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = match args.as_slice() {
+        ["export", world, dir, rest @ ..] => {
+            let format = match rest.first().map(|name| TextFormat::from_name(name)) {
+                Some(Some(format)) => format,
+                Some(None) => {
+                    println!("{SAVE_COMMAND_USAGE}");
+                    return Some(Ok(()));
+                }
+                None => TextFormat::Ron,
+            };
+
+            export_world(&SaveSlot::new(world), Path::new(dir), format)
+                .map(|chunks| println!("Exported world {world} with {chunks} chunks to {dir}"))
+        }
+        ["import", dir, world] => {
+            let slot = SaveSlot::unique(world);
+            import_world(Path::new(dir), &slot).map(|chunks| {
+                println!("Imported {dir} with {chunks} chunks as world {}", slot.name())
+            })
+        }
+        ["export" | "import", ..] => {
+            println!("{SAVE_COMMAND_USAGE}");
+            Ok(())
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let dir = std::env::temp_dir().join("world_export_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+
+        let slot = SaveSlot::new("export round trip");
+        let planet = Planet { noise_seed: 42, lacunarity: 2.5, ..Default::default() };
+        save_data(slot.planet_path(), planet).unwrap();
+
+        let region = PlanetLocation::new(IVec2::new(1, 1));
+        let mut chunk = Chunk::new(region, region.to_world().into());
+        chunk.tiles[3] = TileType::Water;
+        write_chunk_data(&slot, chunk.location, &encode_data(&chunk).unwrap()).unwrap();
+
+        for format in TextFormat::ALL {
+            assert_eq!(export_world(&slot, &dir, format).unwrap(), 1);
+        }
+
+        // Hand edits to the exported files land in the imported save
+        let chunk_file = dir
+            .join(CHUNK_EXPORT_DIR)
+            .join(format!("{}_{}.json", chunk.location.x, chunk.location.y));
+        let mut edited: Chunk = read_text(&chunk_file).unwrap();
+        edited.tiles[4] = TileType::Wall;
+        fs::remove_dir_all(dir.join(CHUNK_EXPORT_DIR)).unwrap();
+        fs::create_dir_all(dir.join(CHUNK_EXPORT_DIR)).unwrap();
+        write_text(&chunk_file, &edited, TextFormat::Json).unwrap();
+
+        let imported = SaveSlot::new("export round trip imported");
+        assert_eq!(import_world(&dir, &imported).unwrap(), 1);
+        assert_eq!(load_planet(&imported).unwrap().noise_seed, 42);
+
+        let data = read_chunk_data(&imported, chunk.location).unwrap().unwrap();
+        let tiles = decode_data::<Chunk>(&data).unwrap().tiles;
+        assert_eq!((tiles[3], tiles[4]), (TileType::Water, TileType::Wall));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::prelude::*;
+
+/// Marks a file as a save of this game.
+pub const SAVE_MAGIC: [u8; 4] = *b"CSAV";
+/// Size in bytes of the [`SaveHeader`] in front of every save.
+pub const SAVE_HEADER_SIZE: usize = 16;
+
+/// The kind of data stored in a save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadType {
+    Planet = 1,
+    Chunk = 2,
+    Metadata = 3,
+    Game = 4,
+    Archive = 5,
+    Region = 6,
+}
+
+impl PayloadType {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            1 => Some(Self::Planet),
+            2 => Some(Self::Chunk),
+            3 => Some(Self::Metadata),
+            4 => Some(Self::Game),
+            5 => Some(Self::Archive),
+            6 => Some(Self::Region),
+            _ => None,
+        }
+    }
+}
+
+/// A type stored in saves. Bump `VERSION` whenever its serialized layout changes, and
+/// register a [`Migration`] upgrading the previous version.
+pub trait SavePayload: Serialize + for<'a> Deserialize<'a> {
+    const PAYLOAD: PayloadType;
+    const VERSION: u32;
+}
+
+/// Header written in front of every save: magic, format version, payload type and a
+/// checksum of the compressed payload, all little endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveHeader {
+    pub version: u32,
+    pub payload: PayloadType,
+    pub checksum: u32,
+}
+
+impl SaveHeader {
+    pub fn new(payload: PayloadType, version: u32, data: &[u8]) -> Self {
+        Self { version, payload, checksum: crc32fast::hash(data) }
+    }
+
+    /// Parses the header at the start of `bytes`. Returns `None` for saves written before
+    /// headers were added.
+    pub fn read(bytes: &[u8]) -> Result<Option<Self>, IOError> {
+        if bytes.len() < SAVE_HEADER_SIZE || bytes[0..4] != SAVE_MAGIC {
+            return Ok(None);
+        }
+
+        let word = |i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+        let payload = match PayloadType::from_u32(word(2)) {
+            Some(payload) => payload,
+            None => return Err(IOError::UnknownPayloadType(word(2))),
+        };
+
+        Ok(Some(Self { version: word(1), payload, checksum: word(3) }))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SAVE_HEADER_SIZE] {
+        let mut bytes = [0; SAVE_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&SAVE_MAGIC);
+        bytes[4..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8..12].copy_from_slice(&(self.payload as u32).to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Returns true if `data` matches the checksum.
+    pub fn verify(&self, data: &[u8]) -> bool { crc32fast::hash(data) == self.checksum }
+}
@@ -0,0 +1,209 @@
+use crate::prelude::*;
+
+/// Upgrades an uncompressed, bincode serialized payload to the next version.
+pub type MigrationFn = fn(Vec<u8>) -> Result<Vec<u8>, IOError>;
+
+/// Upgrades a payload from `from_version` to `from_version + 1`.
+pub struct Migration {
+    pub payload: PayloadType,
+    pub from_version: u32,
+    pub migrate: MigrationFn,
+}
+
+/// Every registered migration. Saves are upgraded one version at a time until they reach
+/// the [`SavePayload::VERSION`] of their type.
+///
+/// Version 0 are saves written before headers were added. Planets match version 1, chunks
+/// still had unsigned coordinates.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { payload: PayloadType::Planet, from_version: 0, migrate: unchanged },
+    Migration { payload: PayloadType::Chunk, from_version: 0, migrate: sign_chunk_location },
+    Migration { payload: PayloadType::Chunk, from_version: 1, migrate: add_chunk_entities },
+    Migration { payload: PayloadType::Chunk, from_version: 2, migrate: palette_chunk_tiles },
+    Migration { payload: PayloadType::Chunk, from_version: 3, migrate: rebase_chunk_entities },
+    Migration { payload: PayloadType::Chunk, from_version: 4, migrate: add_chunk_elevation },
+    Migration { payload: PayloadType::Game, from_version: 1, migrate: rebase_game_player },
+];
+
+/// The layout of chunks v3 and v4, before they stored their elevation.
+#[derive(Serialize, Deserialize)]
+struct ChunkV4 {
+    #[serde(with = "paletted_tiles")]
+    tiles: Vec<TileType>,
+    region: PlanetLocation,
+    location: ChunkLocation,
+    entities: Vec<SavedEntity>,
+}
+
+fn unchanged(raw: Vec<u8>) -> Result<Vec<u8>, IOError> { Ok(raw) }
+
+/// Chunks v1 store their location as signed coordinates, v0 stored two `u64`s.
+fn sign_chunk_location(raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let (tiles, region, (x, y)): (Vec<TileType>, PlanetLocation, (u64, u64)) =
+        bincode::deserialize(&raw).map_err(IOError::FailedToDeserialize)?;
+    let coord = |value: u64| i32::try_from(value).map_err(|_| IOError::SaveFileCorrupted);
+    let location = ChunkLocation::new(coord(x)?, coord(y)?);
+
+    bincode::serialize(&(tiles, region, location)).map_err(IOError::FailedToSerialize)
+}
+
+/// Chunks v2 store the entities standing in them, after the tiles.
+fn add_chunk_entities(mut raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let entities =
+        bincode::serialize(&Vec::<SavedEntity>::new()).map_err(IOError::FailedToSerialize)?;
+    raw.extend(entities);
+    Ok(raw)
+}
+
+/// Chunks v3 store their tiles with a palette, see [`paletted_tiles`].
+fn palette_chunk_tiles(raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let (tiles, region, location, entities): (
+        Vec<TileType>,
+        PlanetLocation,
+        ChunkLocation,
+        Vec<SavedEntity>,
+    ) = bincode::deserialize(&raw).map_err(IOError::FailedToDeserialize)?;
+
+    bincode::serialize(&ChunkV4 { tiles, region, location, entities })
+        .map_err(IOError::FailedToSerialize)
+}
+
+/// Positions used to hold a world-space tile and the region the entity was created in, they
+/// now hold the region the entity stands in and the tile within it.
+fn rebase_position(position: Position) -> Position {
+    Position::from_world(position.tile.to_world())
+}
+
+/// Chunks v4 store entity positions relative to their region.
+fn rebase_chunk_entities(raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let mut chunk: ChunkV4 =
+        bincode::deserialize(&raw).map_err(IOError::FailedToDeserialize)?;
+    for entity in chunk.entities.iter_mut() {
+        entity.position = rebase_position(entity.position);
+    }
+
+    bincode::serialize(&chunk).map_err(IOError::FailedToSerialize)
+}
+
+/// Chunks v5 store the elevation of their tiles. It isn't saved in older chunks, they're
+/// left without one, and get it from the planet when they load, see [`load_chunk`].
+fn add_chunk_elevation(raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let ChunkV4 { tiles, region, location, entities } =
+        bincode::deserialize(&raw).map_err(IOError::FailedToDeserialize)?;
+    let elevation = Vec::new();
+
+    bincode::serialize(&Chunk { tiles, elevation, region, location, entities })
+        .map_err(IOError::FailedToSerialize)
+}
+
+/// Games v2 store the player position relative to its region.
+fn rebase_game_player(raw: Vec<u8>) -> Result<Vec<u8>, IOError> {
+    let mut game: GameSave =
+        bincode::deserialize(&raw).map_err(IOError::FailedToDeserialize)?;
+    game.player.position = rebase_position(game.player.position);
+
+    bincode::serialize(&game).map_err(IOError::FailedToSerialize)
+}
+
+/// Runs the migrations upgrading a `payload` from `version` to `target`.
+pub fn migrate_payload(
+    payload: PayloadType,
+    mut version: u32,
+    target: u32,
+    mut raw: Vec<u8>,
+) -> Result<Vec<u8>, IOError> {
+    while version < target {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.payload == payload && m.from_version == version)
+            .ok_or(IOError::MissingMigration { payload, version })?;
+
+        raw = (migration.migrate)(raw)?;
+        version += 1;
+    }
+
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chunk() -> Chunk {
+        Chunk::new(PlanetLocation::new(IVec2::new(1, 2)), ChunkLocation::new(32, 64))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = encode_data(&chunk()).unwrap();
+        let header = SaveHeader::read(&bytes).unwrap().unwrap();
+        assert_eq!(header.payload, PayloadType::Chunk);
+        assert_eq!(header.version, Chunk::VERSION);
+
+        let decoded = decode_data::<Chunk>(&bytes).unwrap();
+        assert_eq!(decoded.location, chunk().location);
+        assert_eq!(decoded.tiles, chunk().tiles);
+    }
+
+    #[test]
+    fn test_headerless_save_is_migrated() {
+        // Version 0 chunks had unsigned coordinates and no entities
+        let chunk = chunk();
+        let location = (chunk.location.x as u64, chunk.location.y as u64);
+        let raw = bincode::serialize(&(&chunk.tiles, chunk.region, location)).unwrap();
+        let bytes = miniz_oxide::deflate::compress_to_vec(&raw, 6);
+
+        let decoded = decode_data::<Chunk>(&bytes).unwrap();
+        assert_eq!(decoded.location, chunk.location);
+        assert_eq!(decoded.tiles, chunk.tiles);
+        assert!(decoded.elevation.is_empty());
+        assert!(decoded.entities.is_empty());
+    }
+
+    #[test]
+    fn test_positions_are_rebased() {
+        // Version 1 games held the world-space tile of the player
+        let region = PlanetLocation::new(IVec2::new(2, 3));
+        let world = region.to_world() + IVec2::new(300, 5);
+        let player = SavedEntity {
+            position: Position::with_tile_coords(region, world.x, world.y),
+            glyph: None,
+            fov_radius: None,
+            viewer: None,
+            player: true,
+        };
+
+        let data = miniz_oxide::deflate::compress_to_vec(
+            &bincode::serialize(&GameSave { player }).unwrap(),
+            6,
+        );
+        let mut bytes = SaveHeader::new(PayloadType::Game, 1, &data).to_bytes().to_vec();
+        bytes.extend(data);
+
+        let position = decode_data::<GameSave>(&bytes).unwrap().player.position;
+        assert_eq!(position.region, PlanetLocation::new(IVec2::new(3, 3)));
+        assert_eq!(position.to_world(), world);
+    }
+
+    #[test]
+    fn test_rejects_newer_and_corrupt_saves() {
+        let mut bytes = encode_data(&chunk()).unwrap();
+        let data = bytes[SAVE_HEADER_SIZE..].to_vec();
+
+        let newer = SaveHeader::new(PayloadType::Chunk, Chunk::VERSION + 1, &data);
+        bytes[..SAVE_HEADER_SIZE].copy_from_slice(&newer.to_bytes());
+        assert!(matches!(
+            decode_data::<Chunk>(&bytes),
+            Err(IOError::SaveFileTooNew { version, supported })
+                if version == Chunk::VERSION + 1 && supported == Chunk::VERSION
+        ));
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(matches!(decode_data::<Chunk>(&bytes), Err(IOError::ChecksumMismatch)));
+        assert!(matches!(
+            decode_data::<Planet>(&bytes),
+            Err(IOError::WrongPayloadType { .. })
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::prelude::*;
+
+mod atomic;
+mod entities;
+mod error;
+mod export;
+mod header;
+mod migration;
+mod slots;
+mod storage;
+
+pub use atomic::*;
+pub use entities::*;
+pub use error::*;
+pub use export::*;
+pub use header::*;
+pub use migration::*;
+pub use slots::*;
+pub use storage::*;
+
+/// Prefix of the [`SaveStorage`] keys of every world.
+pub const WORLD_DIR: &str = "worlds";
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// IO Checks
+//////////////////////////////////////////////////////////////////////////////////////////
+
+pub fn check_file_exists(filename: &str) -> bool { save_storage().exists(filename) }
+
+pub fn does_chunk_file_exist(slot: &SaveSlot, chunk_id: ChunkLocation) -> bool {
+    has_chunk_data(slot, chunk_id)
+}
+
+/// Returns true if the save or its backup exists.
+pub fn check_save_exists(filename: &str) -> bool {
+    check_file_exists(filename) || check_file_exists(&backup_path(filename))
+}
+
+pub fn does_world_file_exist(slot: &SaveSlot) -> bool {
+    check_save_exists(&slot.planet_path())
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// IO Operations
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Loads a save, falling back to its backup when the save is missing or corrupt. Errors
+/// name the file, and are about the save rather than its backup.
+pub fn load_data<D: SavePayload>(file_path: String) -> Result<D, IOError> {
+    let err = match read_data(&file_path) {
+        Ok(data) => return Ok(data),
+        // An older binary can't read the backup either
+        Err(err @ IOError::SaveFileTooNew { .. }) => return Err(err.in_file(file_path)),
+        Err(err) => err,
+    };
+
+    let backup = backup_path(&file_path);
+    if !check_file_exists(&backup) {
+        return Err(err.in_file(file_path));
+    }
+
+    println!("Failed to load {file_path}: {err}, loading the backup instead");
+    read_data(&backup).map_err(|_| err.in_file(file_path))
+}
+
+fn read_data<D: SavePayload>(file_path: &str) -> Result<D, IOError> {
+    let buffer = save_storage().read(file_path)?;
+    decode_data(&buffer)
+}
+
+/// Saves `data`, keeping the previous save as a backup. The save is written next to the
+/// previous one, which is then renamed to the backup, so a crash at any point leaves either
+/// the save or its backup in place.
+pub fn save_data<D: SavePayload>(file_path: String, data: D) -> Result<(), IOError> {
+    let compressed_bytes = encode_data(&data)?;
+
+    let storage = save_storage();
+    let temp_path = format!("{file_path}.tmp");
+    storage
+        .write(&temp_path, &compressed_bytes)
+        .map_err(|err| err.in_file(temp_path.clone()))?;
+    if storage.exists(&file_path) {
+        let backup = backup_path(&file_path);
+        storage.rename(&file_path, &backup).map_err(|err| err.in_file(backup))?;
+    }
+    storage.rename(&temp_path, &file_path).map_err(|err| err.in_file(file_path))
+}
+
+/// Serializes and compresses `data` behind a [`SaveHeader`], in the format written by
+/// [`save_data`].
+pub fn encode_data<D: SavePayload>(data: &D) -> Result<Vec<u8>, IOError> {
+    let mem_vec = bincode::serialize(data).map_err(IOError::FailedToSerialize)?;
+    let compressed_bytes = miniz_oxide::deflate::compress_to_vec(&mem_vec, 6);
+
+    let mut bytes =
+        SaveHeader::new(D::PAYLOAD, D::VERSION, &compressed_bytes).to_bytes().to_vec();
+    bytes.extend(compressed_bytes);
+    Ok(bytes)
+}
+
+/// Checks the header of bytes written by [`encode_data`], then decompresses, migrates and
+/// deserializes the payload.
+pub fn decode_data<D: SavePayload>(bytes: &[u8]) -> Result<D, IOError> {
+    let (version, compressed_bytes) = match SaveHeader::read(bytes)? {
+        Some(header) => {
+            let data = &bytes[SAVE_HEADER_SIZE..];
+            if header.payload != D::PAYLOAD {
+                return Err(IOError::WrongPayloadType {
+                    expected: D::PAYLOAD,
+                    found: header.payload,
+                });
+            }
+            if !header.verify(data) {
+                return Err(IOError::ChecksumMismatch);
+            }
+            if header.version > D::VERSION {
+                return Err(IOError::SaveFileTooNew {
+                    version: header.version,
+                    supported: D::VERSION,
+                });
+            }
+
+            (header.version, data)
+        }
+        // Written before saves had a header
+        None => (0, bytes),
+    };
+
+    let raw_bytes = miniz_oxide::inflate::decompress_to_vec(compressed_bytes)
+        .map_err(|err| IOError::FailedToDecompressFile(err.status))?;
+    let raw_bytes = migrate_payload(D::PAYLOAD, version, D::VERSION, raw_bytes)?;
+
+    bincode::deserialize(&raw_bytes).map_err(IOError::FailedToDeserialize)
+}
+
+/// Picks the [`SaveStorage`] saves are kept in, see [`StorageKind::from_env`].
+pub fn setup_io_access() -> Result<(), IOError> {
+    select_save_storage(StorageKind::from_env())
+}
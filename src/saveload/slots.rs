@@ -0,0 +1,230 @@
+use crate::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PLANET_FILE: &str = "world.dat";
+const METADATA_FILE: &str = "world.meta";
+const GAME_FILE: &str = "game.dat";
+const SLOT_CHUNK_DIR: &str = "chunks";
+const SLOT_REGION_DIR: &str = "regions";
+
+/// Where chunks were saved before worlds had their own slot.
+const LEGACY_CHUNK_DIR: &str = "chunks";
+
+/// Handle to a named world save. Every world lives in its own directory under the worlds
+/// prefix of the [`SaveStorage`], holding the planet, its chunks and a [`WorldMetadata`]
+/// file. The slot of the world being played is available as a resource.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SaveSlot {
+    name: String,
+}
+
+impl SaveSlot {
+    /// Handle to the slot named `name`, which doesn't have to exist yet. Characters that
+    /// can't be used in a directory name are dropped.
+    pub fn new(name: &str) -> Self {
+        let name = name
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+            .collect::<String>();
+        let name = name.trim();
+
+        Self { name: if name.is_empty() { String::from("world") } else { name.to_string() } }
+    }
+
+    /// Handle to a slot that doesn't exist yet, named `name` with a number appended if the
+    /// name is taken.
+    pub fn unique(name: &str) -> Self {
+        let base = Self::new(name);
+        let mut slot = base.clone();
+        let mut suffix = 2;
+        while slot.exists() {
+            slot = Self::new(&format!("{} {suffix}", base.name));
+            suffix += 1;
+        }
+
+        slot
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn dir(&self) -> String { format!("{WORLD_DIR}/{}", self.name) }
+
+    pub fn planet_path(&self) -> String { format!("{}/{PLANET_FILE}", self.dir()) }
+
+    pub fn metadata_path(&self) -> String { format!("{}/{METADATA_FILE}", self.dir()) }
+
+    pub fn game_path(&self) -> String { format!("{}/{GAME_FILE}", self.dir()) }
+
+    pub fn chunk_dir(&self) -> String { format!("{}/{SLOT_CHUNK_DIR}", self.dir()) }
+
+    pub fn chunk_path(&self, chunk_file_name: &str) -> String {
+        format!("{}/{chunk_file_name}", self.chunk_dir())
+    }
+
+    /// Where the tiles of an evicted region are saved.
+    pub fn region_path(&self, location: PlanetLocation) -> String {
+        format!("{}/{SLOT_REGION_DIR}/{}_{}.dat", self.dir(), location.x, location.y)
+    }
+
+    /// Every key stored in the slot.
+    pub fn keys(&self) -> Vec<String> { save_storage().list(&format!("{}/", self.dir())) }
+
+    pub fn exists(&self) -> bool { !self.keys().is_empty() }
+
+    /// Total size in bytes of the slot's files.
+    pub fn size_on_disk(&self) -> u64 {
+        let storage = save_storage();
+        self.keys().iter().map(|key| storage.size(key)).sum()
+    }
+
+    /// Deletes the slot and every file in it.
+    pub fn delete(&self) -> Result<(), IOError> {
+        let storage = save_storage();
+        self.keys().iter().try_for_each(|key| storage.delete(key))?;
+        storage.flush()
+    }
+
+    /// Copies the slot into a new slot named after it.
+    pub fn duplicate(&self) -> Result<SaveSlot, IOError> {
+        let copy = SaveSlot::unique(&format!("{} copy", self.name));
+        let storage = save_storage();
+        for key in self.keys() {
+            let target = format!("{}{}", copy.dir(), &key[self.dir().len()..]);
+            storage.write(&target, &storage.read(&key)?)?;
+        }
+
+        if let Ok(mut metadata) = load_metadata(&copy) {
+            metadata.created = unix_timestamp();
+            save_metadata(&copy, &metadata)?;
+        }
+
+        Ok(copy)
+    }
+}
+
+/// Lists every save slot, most recently played first.
+pub fn list_save_slots() -> Vec<SaveSlot> {
+    let prefix = format!("{WORLD_DIR}/");
+    let names = save_storage()
+        .list(&prefix)
+        .iter()
+        .filter_map(|key| {
+            key[prefix.len()..].split_once('/').map(|(name, _)| name.to_string())
+        })
+        .collect::<std::collections::BTreeSet<_>>();
+
+    let mut slots = names
+        .iter()
+        .map(|name| SaveSlot::new(name))
+        .map(|slot| {
+            let last_played = load_metadata(&slot).map_or(0, |metadata| metadata.last_played);
+            (last_played, slot)
+        })
+        .collect::<Vec<_>>();
+    slots.sort_by(|(a_played, a), (b_played, b)| {
+        b_played.cmp(a_played).then(a.name.cmp(&b.name))
+    });
+
+    slots.into_iter().map(|(_, slot)| slot).collect()
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Metadata
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Describes a world save without loading its planet. Timestamps are seconds since the
+/// unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldMetadata {
+    pub seed: String,
+    pub lacunarity: f32,
+    pub created: u64,
+    pub last_played: u64,
+    pub embark: Option<PlanetLocation>,
+}
+
+impl SavePayload for WorldMetadata {
+    const PAYLOAD: PayloadType = PayloadType::Metadata;
+    const VERSION: u32 = 1;
+}
+
+impl WorldMetadata {
+    pub fn new(seed: &str, lacunarity: f32) -> Self {
+        let now = unix_timestamp();
+        Self {
+            seed: seed.to_string(),
+            lacunarity,
+            created: now,
+            last_played: now,
+            embark: None,
+        }
+    }
+
+    /// Marks the world as played now.
+    pub fn touch(&mut self) { self.last_played = unix_timestamp(); }
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+pub fn load_metadata(slot: &SaveSlot) -> Result<WorldMetadata, IOError> {
+    load_data(slot.metadata_path())
+}
+
+pub fn save_metadata(slot: &SaveSlot, metadata: &WorldMetadata) -> Result<(), IOError> {
+    save_data(slot.metadata_path(), metadata.clone())?;
+    save_storage().flush()
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Migration
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Moves the single world saved before slots existed into a slot of its own. Returns the
+/// new slot, or `None` if there was no such save.
+pub fn migrate_legacy_save() -> Result<Option<SaveSlot>, IOError> {
+    let legacy_planet = format!("{WORLD_DIR}/{PLANET_FILE}");
+    if !check_save_exists(&legacy_planet) {
+        return Ok(None);
+    }
+
+    let slot = SaveSlot::unique("world");
+    let storage = save_storage();
+
+    let mut moves = vec![
+        (legacy_planet.clone(), slot.planet_path()),
+        (backup_path(&legacy_planet), backup_path(&slot.planet_path())),
+    ];
+    for key in storage.list(&format!("{LEGACY_CHUNK_DIR}/")) {
+        let target = slot.chunk_path(&key[LEGACY_CHUNK_DIR.len() + 1..]);
+        moves.push((key, target));
+    }
+
+    for (from, to) in moves.into_iter().filter(|(from, _)| storage.exists(from)) {
+        storage.write(&to, &storage.read(&from)?)?;
+        storage.delete(&from)?;
+    }
+
+    let mut metadata = WorldMetadata::new("", 0.0);
+    if let Ok(planet) = load_data::<Planet>(slot.planet_path()) {
+        metadata.seed = planet.noise_seed.to_string();
+        metadata.lacunarity = planet.lacunarity;
+    }
+    save_metadata(&slot, &metadata)?;
+
+    Ok(Some(slot))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slot_names() {
+        assert_eq!(SaveSlot::new("My World").name(), "My World");
+        assert_eq!(SaveSlot::new("../etc/passwd").name(), "etcpasswd");
+        assert_eq!(SaveSlot::new(" / ").name(), "world");
+        assert_eq!(SaveSlot::new("Test Seed").planet_path(), "worlds/Test Seed/world.dat");
+    }
+}
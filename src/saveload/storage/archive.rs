@@ -0,0 +1,103 @@
+use crate::prelude::*;
+use parking_lot::RwLock;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The contents of an archive, saved like any other payload.
+#[derive(Default, Serialize, Deserialize)]
+struct SaveArchive {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl SavePayload for SaveArchive {
+    const PAYLOAD: PayloadType = PayloadType::Archive;
+    const VERSION: u32 = 1;
+}
+
+/// Keeps every save in a single archive file, which is easy to move around or upload. The
+/// archive is held in memory, changes are only written to disk when it's flushed, as the
+/// whole archive is rewritten each time.
+pub struct ArchiveStorage {
+    path: PathBuf,
+    archive: RwLock<SaveArchive>,
+    dirty: AtomicBool,
+}
+
+impl ArchiveStorage {
+    /// Opens the archive at `path`, which is created on the first flush.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IOError> {
+        let path = path.as_ref().to_path_buf();
+        let archive = match std::fs::read(&path) {
+            Ok(bytes) => decode_data(&bytes),
+            Err(_) if !path.exists() => Ok(SaveArchive::default()),
+            Err(err) => Err(IOError::FailedToReadFile(err)),
+        }
+        .map_err(|err| err.in_file(path.to_string_lossy()))?;
+
+        Ok(Self { path, archive: RwLock::new(archive), dirty: AtomicBool::new(false) })
+    }
+}
+
+impl SaveStorage for ArchiveStorage {
+    fn read(&self, key: &str) -> Result<Vec<u8>, IOError> {
+        self.archive.read().files.get(key).cloned().ok_or(IOError::SaveFileDoesNotExist)
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> Result<(), IOError> {
+        self.archive.write().files.insert(key.to_string(), bytes.to_vec());
+        self.dirty.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn exists(&self, key: &str) -> bool { self.archive.read().files.contains_key(key) }
+
+    fn list(&self, prefix: &str) -> Vec<String> {
+        let archive = self.archive.read();
+        archive
+            .files
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    fn delete(&self, key: &str) -> Result<(), IOError> {
+        self.archive.write().files.remove(key).ok_or(IOError::SaveFileDoesNotExist)?;
+        self.dirty.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IOError> {
+        let mut archive = self.archive.write();
+        let bytes = archive.files.remove(from).ok_or(IOError::SaveFileDoesNotExist)?;
+        archive.files.insert(to.to_string(), bytes);
+        self.dirty.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Rewrites the archive on disk if anything changed. The changes stay pending if the
+    /// write fails, so the next flush tries again.
+    fn flush(&self) -> Result<(), IOError> {
+        // Holding the lock keeps changes from landing between the encode and the write
+        let archive = self.archive.read();
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let result = encode_data(&*archive)
+            .and_then(|bytes| write_atomic(&self.path, &bytes))
+            .map_err(|err| err.in_file(self.path.to_string_lossy()));
+        if result.is_err() {
+            self.dirty.store(true, Ordering::Release);
+        }
+
+        result
+    }
+
+    fn size(&self, key: &str) -> u64 {
+        self.archive.read().files.get(key).map_or(0, |bytes| bytes.len() as u64)
+    }
+}
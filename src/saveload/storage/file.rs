@@ -0,0 +1,101 @@
+use crate::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Stores every key as a file under a root directory, written with [`write_atomic`].
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self { Self { root: root.into() } }
+
+    fn path(&self, key: &str) -> PathBuf { self.root.join(key) }
+
+    /// Collects the keys of every file under `dir`.
+    fn collect_keys(&self, dir: &Path, keys: &mut Vec<String>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.is_dir() {
+                self.collect_keys(&path, keys);
+            } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                let parts =
+                    relative.iter().map(|part| part.to_string_lossy()).collect::<Vec<_>>();
+                keys.push(parts.join("/"));
+            }
+        }
+    }
+}
+
+impl SaveStorage for FileStorage {
+    fn read(&self, key: &str) -> Result<Vec<u8>, IOError> {
+        let path = self.path(key);
+        if !path.is_file() {
+            return Err(IOError::SaveFileDoesNotExist);
+        }
+
+        fs::read(path).map_err(IOError::FailedToReadFile)
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> Result<(), IOError> {
+        let path = self.path(key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(IOError::FailedToCreateDir)?;
+        }
+
+        write_atomic(&path, bytes)
+    }
+
+    fn exists(&self, key: &str) -> bool { self.path(key).is_file() }
+
+    fn list(&self, prefix: &str) -> Vec<String> {
+        // Only walk the deepest directory the prefix names
+        let dir = match prefix.rfind('/') {
+            Some(end) => self.path(&prefix[..end]),
+            None => self.root.clone(),
+        };
+
+        let mut keys = Vec::new();
+        self.collect_keys(&dir, &mut keys);
+        keys.retain(|key| key.starts_with(prefix));
+        keys
+    }
+
+    fn delete(&self, key: &str) -> Result<(), IOError> {
+        let path = self.path(key);
+        fs::remove_file(&path).map_err(IOError::FailedToDeleteFile)?;
+
+        // Drop directories left empty, so deleted worlds don't linger
+        let mut dir = path.parent();
+        while let Some(parent) = dir.filter(|dir| *dir != self.root) {
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IOError> {
+        let (from, to) = (self.path(from), self.path(to));
+        if !from.is_file() {
+            return Err(IOError::SaveFileDoesNotExist);
+        }
+        if let Some(dir) = to.parent() {
+            fs::create_dir_all(dir).map_err(IOError::FailedToCreateDir)?;
+        }
+
+        fs::rename(&from, &to).map_err(IOError::FailedToWriteFile)?;
+        sync_parent_dir(&to);
+        Ok(())
+    }
+
+    fn size(&self, key: &str) -> u64 { fs::metadata(self.path(key)).map_or(0, |m| m.len()) }
+}
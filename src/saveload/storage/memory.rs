@@ -0,0 +1,49 @@
+use crate::prelude::*;
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+
+/// Keeps saves in memory, for tests and headless runs. Nothing survives the process.
+#[derive(Default)]
+pub struct MemoryStorage {
+    files: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl SaveStorage for MemoryStorage {
+    fn read(&self, key: &str) -> Result<Vec<u8>, IOError> {
+        self.files.read().get(key).cloned().ok_or(IOError::SaveFileDoesNotExist)
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> Result<(), IOError> {
+        self.files.write().insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self, key: &str) -> bool { self.files.read().contains_key(key) }
+
+    fn list(&self, prefix: &str) -> Vec<String> {
+        let files = self.files.read();
+        files
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    fn delete(&self, key: &str) -> Result<(), IOError> {
+        match self.files.write().remove(key) {
+            Some(_) => Ok(()),
+            None => Err(IOError::SaveFileDoesNotExist),
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IOError> {
+        let mut files = self.files.write();
+        let bytes = files.remove(from).ok_or(IOError::SaveFileDoesNotExist)?;
+        files.insert(to.to_string(), bytes);
+        Ok(())
+    }
+
+    fn size(&self, key: &str) -> u64 {
+        self.files.read().get(key).map_or(0, |bytes| bytes.len() as u64)
+    }
+}
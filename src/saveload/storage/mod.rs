@@ -0,0 +1,157 @@
+use crate::prelude::*;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+mod archive;
+mod file;
+mod memory;
+
+pub use archive::*;
+pub use file::*;
+pub use memory::*;
+
+/// Root directory of the filesystem storage.
+pub const SAVE_DIR: &str = "savegame";
+/// The archive used by the archive storage.
+pub const SAVE_ARCHIVE: &str = "savegame.sav";
+
+lazy_static! {
+    static ref SAVE_STORAGE: RwLock<Arc<dyn SaveStorage>> = RwLock::new(default_storage());
+}
+
+/// Where saves are kept. Keys are `/` separated paths, such as `worlds/My World/world.dat`.
+/// Every write replaces the whole value, and must leave the old value in place if it fails.
+/// Storages may hold changes in memory until they're flushed.
+pub trait SaveStorage: Send + Sync {
+    fn read(&self, key: &str) -> Result<Vec<u8>, IOError>;
+
+    fn write(&self, key: &str, bytes: &[u8]) -> Result<(), IOError>;
+
+    fn exists(&self, key: &str) -> bool;
+
+    /// Every key starting with `prefix`, in no particular order.
+    fn list(&self, prefix: &str) -> Vec<String>;
+
+    fn delete(&self, key: &str) -> Result<(), IOError>;
+
+    /// Moves the value at `from` to `to`, replacing any value already there.
+    fn rename(&self, from: &str, to: &str) -> Result<(), IOError>;
+
+    /// Writes the changes held in memory, if any.
+    fn flush(&self) -> Result<(), IOError> { Ok(()) }
+
+    /// Size in bytes of the value stored at `key`, or 0 if there is none.
+    fn size(&self, key: &str) -> u64 { self.read(key).map_or(0, |bytes| bytes.len() as u64) }
+}
+
+/// Which [`SaveStorage`] to use, picked with the `SAVE_STORAGE` environment variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    /// One file per key under [`SAVE_DIR`].
+    Files,
+    /// Nothing is written to disk, saves are lost on exit.
+    Memory,
+    /// Every key in the single [`SAVE_ARCHIVE`] file.
+    Archive,
+}
+
+impl StorageKind {
+    pub fn from_env() -> Self {
+        match std::env::var("SAVE_STORAGE").as_deref() {
+            Ok("memory") => Self::Memory,
+            Ok("archive") => Self::Archive,
+            // There is no filesystem to write to on the web
+            _ if cfg!(target_arch = "wasm32") => Self::Memory,
+            _ => Self::Files,
+        }
+    }
+}
+
+/// Tests never touch the disk unless they pick a storage themselves.
+fn default_storage() -> Arc<dyn SaveStorage> {
+    if cfg!(test) || cfg!(target_arch = "wasm32") {
+        Arc::new(MemoryStorage::default())
+    } else {
+        Arc::new(FileStorage::new(SAVE_DIR))
+    }
+}
+
+pub fn save_storage() -> Arc<dyn SaveStorage> { SAVE_STORAGE.read().clone() }
+
+pub fn set_save_storage(storage: impl SaveStorage + 'static) {
+    *SAVE_STORAGE.write() = Arc::new(storage);
+}
+
+/// Installs the storage of the given kind.
+pub fn select_save_storage(kind: StorageKind) -> Result<(), IOError> {
+    match kind {
+        StorageKind::Files => set_save_storage(FileStorage::new(SAVE_DIR)),
+        StorageKind::Memory => set_save_storage(MemoryStorage::default()),
+        StorageKind::Archive => set_save_storage(ArchiveStorage::open(SAVE_ARCHIVE)?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exercise(storage: &dyn SaveStorage) {
+        assert!(!storage.exists("worlds/a/world.dat"));
+        assert!(matches!(
+            storage.read("worlds/a/world.dat"),
+            Err(IOError::SaveFileDoesNotExist)
+        ));
+
+        storage.write("worlds/a/world.dat", &[1, 2, 3]).unwrap();
+        storage.write("worlds/a/chunks/0_0.region", &[4]).unwrap();
+        storage.write("worlds/b/world.dat", &[5]).unwrap();
+        storage.write("worlds/a/world.dat", &[6, 7]).unwrap();
+
+        assert!(storage.exists("worlds/a/world.dat"));
+        assert_eq!(storage.read("worlds/a/world.dat").unwrap(), vec![6, 7]);
+        assert_eq!(storage.size("worlds/a/world.dat"), 2);
+
+        let mut keys = storage.list("worlds/a/");
+        keys.sort();
+        assert_eq!(keys, vec!["worlds/a/chunks/0_0.region", "worlds/a/world.dat"]);
+
+        storage.rename("worlds/b/world.dat", "worlds/c/world.dat").unwrap();
+        assert!(!storage.exists("worlds/b/world.dat"));
+        assert_eq!(storage.read("worlds/c/world.dat").unwrap(), vec![5]);
+
+        storage.delete("worlds/a/world.dat").unwrap();
+        assert!(!storage.exists("worlds/a/world.dat"));
+        assert_eq!(storage.list("worlds/").len(), 2);
+        storage.flush().unwrap();
+    }
+
+    #[test]
+    fn test_memory_storage() { exercise(&MemoryStorage::default()); }
+
+    #[test]
+    fn test_file_storage() {
+        let root = std::env::temp_dir().join("save_storage_files");
+        let _ = std::fs::remove_dir_all(&root);
+        exercise(&FileStorage::new(&root));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_archive_storage() {
+        let path = std::env::temp_dir().join("save_storage_archive.sav");
+        let _ = std::fs::remove_file(&path);
+        exercise(&ArchiveStorage::open(&path).unwrap());
+
+        // Everything written is read back from the archive once flushed
+        let reopened = ArchiveStorage::open(&path).unwrap();
+        assert_eq!(reopened.read("worlds/c/world.dat").unwrap(), vec![5]);
+        reopened.write("worlds/d/world.dat", &[8]).unwrap();
+        assert!(!ArchiveStorage::open(&path).unwrap().exists("worlds/d/world.dat"));
+        reopened.flush().unwrap();
+        assert!(ArchiveStorage::open(&path).unwrap().exists("worlds/d/world.dat"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
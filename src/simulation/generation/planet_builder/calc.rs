@@ -54,6 +54,28 @@ pub fn noise_lat(world_y: usize, region_y: usize) -> f32 {
     (y_extent * 180.0) + sub_y - 90.0
 }
 
+/// Radius of a sphere where one unit along the surface is one degree, so noise sampled on it
+/// has the same scale as noise sampled in degrees.
+pub const DEGREE_SPHERE_RADIUS: f32 = 180.0 / std::f32::consts::PI;
+
+/// Latitude and longitude of a world-space tile. Tiles are spaced evenly over the whole
+/// planet, so tiles that touch across a region edge are just as close in noise space.
+pub fn world_tile_lat_lon(world: IVec2) -> (f32, f32) {
+    let planet_tile_height = (WORLD_HEIGHT * REGION_HEIGHT) as f32;
+    let x = world.x.rem_euclid(PLANET_TILE_WIDTH) as f32;
+    (
+        (world.y as f32 / planet_tile_height * 180.0) - 90.0,
+        (x / PLANET_TILE_WIDTH as f32 * 360.0) - 180.0,
+    )
+}
+
+/// Point on a sphere of `radius` under a world-space tile. Noise sampled there is continuous
+/// across region edges and around the east/west edge of the planet.
+pub fn world_tile_sphere(world: IVec2, radius: f32) -> (f32, f32, f32) {
+    let (lat, lon) = world_tile_lat_lon(world);
+    sphere_vertex(radius, Degrees::new(lat), Degrees::new(lon))
+}
+
 pub fn average_temperature_by_latitude(lat: Degrees) -> f32 {
     // Source: https://davidwaltham.com/global-warming-model/
     const AVERAGE_EQUATORIAL_C: f32 = 30.0;
@@ -122,7 +122,8 @@ impl PlanetBuilder {
             && PLANET_GEN.read().status != PlanetBuilderStatus::Initializing
     }
 
-    pub fn save_planet(&self) {
+    /// Saves the generated planet into a new save slot.
+    pub fn save_planet(&self, slot: SaveSlot, metadata: WorldMetadata) {
         println!("Saving...");
         if PLANET_GEN.read().planet.is_some() {
             let mut write_lock = PLANET_GEN.write();
@@ -130,7 +131,10 @@ impl PlanetBuilder {
 
             std::thread::spawn(move || {
                 update_status(PlanetBuilderStatus::Saving);
-                save_planet(planet.unwrap());
+                save_planet(&slot, planet.unwrap());
+                if let Err(err) = save_metadata(&slot, &metadata) {
+                    println!("Error saving world metadata: {}", err.report());
+                }
             });
         }
     }
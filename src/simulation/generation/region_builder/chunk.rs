@@ -0,0 +1,33 @@
+use crate::prelude::*;
+
+/// Builds a single chunk straight from the global planet, without generating the rest
+/// of its region. Chunks outside of the planet bounds are left as bare floor.
+pub fn build_chunk(chunk_id: ChunkLocation) -> Chunk {
+    let planet_loc = chunk_id.to_planet_location();
+    let mut chunk = Chunk::new(planet_loc, chunk_id);
+
+    if !planet_loc.is_on_planet() {
+        return chunk;
+    }
+
+    // Terrain, materials and vegetation come from the region populator, so the chunk
+    // matches the one generated with its region
+    let region_idx = planet_loc.to_region_index();
+    let region_chunk = chunk_id - ChunkLocation::from(planet_loc.to_world());
+    let populated = populate_region_chunk(region_idx, region_chunk);
+    chunk.tiles = populated.tiles;
+    chunk.elevation = populated.elevation;
+    chunk
+}
+
+/// The elevation of a chunk's tiles, from the global planet. Chunks outside of the planet
+/// bounds are flat.
+pub fn chunk_elevation(chunk_id: ChunkLocation) -> Vec<u32> {
+    let planet_loc = chunk_id.to_planet_location();
+    if !planet_loc.is_on_planet() {
+        return vec![0; TILES_PER_CHUNK];
+    }
+
+    let region_chunk = chunk_id - ChunkLocation::from(planet_loc.to_world());
+    populate_region_chunk(planet_loc.to_region_index(), region_chunk).elevation
+}
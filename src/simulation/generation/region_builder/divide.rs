@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
-pub fn divide_into_chunks(region_id: PlanetLocation) {
+/// Saves the chunks of a generated region. Chunks the slot already holds are left alone, they
+/// may have been edited since the region was first generated.
+pub fn divide_into_chunks(region_id: PlanetLocation, slot: &SaveSlot) {
     let region_idx = &region_id.to_region_index();
     let mut region_lock = REGIONS.write();
     if let Some(region) = region_lock.regions.get_mut(region_idx) {
@@ -8,6 +10,18 @@ pub fn divide_into_chunks(region_id: PlanetLocation) {
 
         for chunk_location in AllChunksIterator::new() {
             let actual_chunk_location = region_chunk_base + chunk_location;
+            match read_chunk_data(slot, actual_chunk_location) {
+                Ok(None) => {}
+                Ok(Some(_)) => continue,
+                Err(err) => {
+                    println!(
+                        "Not replacing chunk {actual_chunk_location:?}: {}",
+                        err.in_chunk(actual_chunk_location).report()
+                    );
+                    continue;
+                }
+            }
+
             let mut chunk = Chunk::new(region_id, actual_chunk_location);
 
             ChunkIterator::new(chunk_location)
@@ -15,9 +29,10 @@ pub fn divide_into_chunks(region_id: PlanetLocation) {
                 .enumerate()
                 .for_each(|(idx, region_tile_idx)| {
                     chunk.tiles[idx] = region.tiles[region_tile_idx];
+                    chunk.elevation[idx] = region.elevation[region_tile_idx];
                 });
 
-            save_chunk(&chunk)
+            save_chunk(slot, &chunk)
         }
     }
 }
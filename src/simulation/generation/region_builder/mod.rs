@@ -1,30 +1,34 @@
 use std::time::Duration;
 
 use crate::prelude::*;
-use bracket_random::prelude::RandomNumberGenerator;
+use bevy::tasks::AsyncComputeTaskPool;
 use lazy_static::lazy_static;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 
+mod chunk;
 mod divide;
 mod plants;
+mod random;
+
+pub use chunk::*;
+pub use plants::*;
+pub use random::*;
 
 lazy_static! {
     static ref REGION_GEN: Lazy<RwLock<RegionGen>> =
         Lazy::new(|| RwLock::new(RegionGen::new()));
 }
 
-#[allow(dead_code)]
 #[derive(Default, PartialEq)]
 pub enum RegionBuilderStatus {
     #[default]
     Initializing,
-    Chunking,
+    Chunking {
+        done: usize,
+    },
     Loaded,
-    Water,
     // Ramping,
-    Vegetation,
-    Trees,
     // Crashing,
     // Debris,
     Dividing,
@@ -46,24 +50,24 @@ pub struct RegionBuilder {
     planet: Planet,
     started: bool,
     crash_site: PlanetLocation,
+    slot: SaveSlot,
 }
 
 impl RegionBuilder {
-    pub fn new(planet: Planet, crash_site: PlanetLocation) -> Self {
-        Self { planet, crash_site, started: false }
+    pub fn new(planet: Planet, crash_site: PlanetLocation, slot: SaveSlot) -> Self {
+        Self { planet, crash_site, slot, started: false }
     }
 
     pub fn status(&self) -> String {
         match REGION_GEN.read().status {
             RegionBuilderStatus::Initializing => String::from("Initializing"),
-            RegionBuilderStatus::Chunking => String::from("Dividing & Conquering"),
+            RegionBuilderStatus::Chunking { done } => {
+                format!("Dividing & Conquering ({done}/{CHUNKS_PER_REGION} chunks)")
+            }
             RegionBuilderStatus::Loaded => String::from("Region activated, making it pretty"),
             // RegionBuilderStatus::Ramping => String::from("Smoothing Rough Edges"),
             // RegionBuilderStatus::Crashing => String::from("Crash Landing"),
-            RegionBuilderStatus::Water => String::from("Just Add Water"),
-            RegionBuilderStatus::Vegetation => String::from("Re-seeding the lawn"),
             // RegionBuilderStatus::Debris => String::from("Making a terrible mess"),
-            RegionBuilderStatus::Trees => String::from("Planting trees"),
             RegionBuilderStatus::Dividing => String::from("Dividing into chunks..."),
             RegionBuilderStatus::Done => String::from("Done"),
         }
@@ -74,41 +78,35 @@ impl RegionBuilder {
             self.started = true;
             let p = self.planet.clone();
             let c = self.crash_site;
+            let slot = self.slot.clone();
 
-            std::thread::spawn(move || build_region(p, c));
+            std::thread::spawn(move || build_region(p, c, slot));
         }
     }
 
     pub fn is_done(&self) -> bool { REGION_GEN.read().status == RegionBuilderStatus::Done }
 }
 
-fn build_region(planet: Planet, planet_idx: PlanetLocation) {
+fn build_region(planet: Planet, planet_idx: PlanetLocation, slot: SaveSlot) {
     println!("Building region");
     set_global_planet(planet);
-    update_status(RegionBuilderStatus::Chunking);
+    update_status(RegionBuilderStatus::Chunking { done: 0 });
     spawn_playable_region(planet_idx);
 
-    set_tiles(planet_idx);
+    // Vegetation and trees are rolled with the rest of each chunk, like streamed regions
+    populate_region(planet_idx);
     update_status(RegionBuilderStatus::Loaded);
     std::thread::sleep(Duration::from_millis(500));
 
     // Beaches
 
-    // Vegetation
-    println!("Veggies");
-    update_status(RegionBuilderStatus::Vegetation);
-    plants::grow_plants(planet_idx);
-    std::thread::sleep(Duration::from_millis(500));
-
-    // Trees
-    println!("Trees");
-    update_status(RegionBuilderStatus::Trees);
-    plants::plant_trees(planet_idx);
-
     // Divide
     println!("Divide");
     update_status(RegionBuilderStatus::Dividing);
-    divide::divide_into_chunks(planet_idx);
+    divide::divide_into_chunks(planet_idx, &slot);
+    if let Some(region) = REGIONS.write().get_region_mut(planet_idx.to_region_index()) {
+        region.status = RegionStatus::Ready;
+    }
 
     update_status(RegionBuilderStatus::Done);
 }
@@ -116,77 +114,152 @@ fn build_region(planet: Planet, planet_idx: PlanetLocation) {
 pub fn spawn_playable_region(location: PlanetLocation) {
     let index = location.to_region_index();
     let mut region_lock = REGIONS.write();
-    region_lock.regions.insert(index, Region::new(location));
+
+    // Keep the region streaming from generating it a second time
+    let mut region = Region::new(location);
+    region.status = RegionStatus::Generating;
+    region_lock.regions.insert(index, region);
 }
 
-pub fn set_tiles(planet_idx: PlanetLocation) {
-    let region_id = &planet_idx.to_region_index();
+/// Populates every chunk of the region on the [`AsyncComputeTaskPool`], reporting progress
+/// as each chunk completes. The chunks are then copied into the region in a fixed order, so
+/// the output only depends on the planet, not on the order the chunks finish in.
+pub fn populate_region(planet_idx: PlanetLocation) {
+    let region_idx = planet_idx.to_region_index();
+    let task_pool = AsyncComputeTaskPool::get();
 
-    let mut region_lock = REGIONS.write();
-    if let Some(region) = region_lock.regions.get_mut(region_id) {
-        // Obtain resources
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for (index, chunk) in AllChunksIterator::new().enumerate() {
+        let sender = sender.clone();
+        task_pool
+            .spawn(async move {
+                let _ = sender.send((index, populate_region_chunk(region_idx, chunk)));
+            })
+            .detach();
+    }
+    drop(sender);
+
+    let mut chunks = (0..CHUNKS_PER_REGION).map(|_| None).collect::<Vec<_>>();
+    for (done, (index, chunk)) in receiver.iter().enumerate() {
+        chunks[index] = Some(chunk);
+        update_status(RegionBuilderStatus::Chunking { done: done + 1 });
+    }
+
+    if let Some(region) = REGIONS.write().get_region_mut(region_idx) {
+        for chunk in chunks.iter().flatten() {
+            apply_region_chunk(region, chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parallel_population_matches_terrain() {
+        setup_test_planet();
+        let location = PlanetLocation::new(IVec2::new(5, 7));
+        spawn_playable_region(location);
+        populate_region(location);
+
+        let regions = REGIONS.read();
+        let region = regions.get_region(location.to_region_index()).unwrap();
+        assert_eq!(region.status, RegionStatus::Ready);
+
+        // Every tile lands where the terrain at its world position says, whichever chunk
+        // populated it
         let plock = PLANET_STORE.read();
         let planet = plock.planet.as_ref().unwrap();
-        let strata = plock.strata.as_ref().unwrap();
         let noise = plock.height_noise.as_ref().unwrap();
-        let cell_noise = plock.material_noise.as_ref().unwrap();
-
-        let tile_x = region_id % WORLD_WIDTH;
-        let tile_y = region_id / WORLD_WIDTH;
-        let biome_idx = planet.landblocks[*region_id].biome_idx;
-        let biome = &RAWS.read().biomes.areas[biome_idx];
-
-        // Determine base altitudes for the region
-        let mut altitudes = vec![0; REGION_WIDTH * REGION_HEIGHT];
-        for y in 0..REGION_HEIGHT {
-            for x in 0..REGION_WIDTH {
-                let altitude = cell_altitude(noise, tile_x, tile_y, x, y);
-                let altitude_idx = (y * REGION_WIDTH) + x;
-                altitudes[altitude_idx] = altitude;
-            }
-        }
+        let origin = location.to_world();
+        for y in 0..REGION_HEIGHT as i32 {
+            for x in 0..REGION_WIDTH as i32 {
+                let world = origin + IVec2::new(x, y);
+                let elevation = cell_altitude(noise, world);
+                let rise = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .map(|(dx, dy)| cell_altitude(noise, world + IVec2::new(*dx, *dy)))
+                    .max()
+                    .unwrap()
+                    .saturating_sub(elevation);
 
-        // Build a local RNG
-        let mut rng = RandomNumberGenerator::seeded(
-            planet.noise_seed
-                + ((tile_y * REGION_WIDTH * CHUNKS_PER_REGION)
-                    + (tile_x * REGION_WIDTH * CHUNK_WIDTH)
-                    + (planet_idx.y as usize * REGION_WIDTH)
-                    + planet_idx.x as usize) as u64,
-        );
-
-        for cy in 0..REGION_HEIGHT {
-            let ry = cy + planet_idx.y as usize;
-            for cx in 0..REGION_WIDTH {
-                let rx = cx + planet_idx.x as usize;
-                let idx = mapidx(cx, cy);
-
-                // Soil or sand
-                let n =
-                    cell_noise.get_noise(noise_lon(tile_y, ry * 2), noise_lat(tile_x, rx * 2));
-
-                if rng.roll_dice(1, 100) < biome.soils.soil {
-                    region.tiles[idx] = TileType::Soil;
-                    region.material[idx] = pick_material(&strata.soils, n);
-                } else if rng.roll_dice(1, 100) < biome.soils.sand {
-                    region.tiles[idx] = TileType::Sand;
-                    region.material[idx] = pick_material(&strata.sand, n);
+                let idx = mapidx(x as usize, y as usize);
+                assert_eq!(region.elevation[idx], elevation, "tile {x},{y}");
+                match terrain_tile(elevation, rise, planet.water_height) {
+                    Some(tile) => assert_eq!(region.tiles[idx], tile, "tile {x},{y}"),
+                    None => assert!(
+                        matches!(
+                            region.tiles[idx],
+                            TileType::Floor
+                                | TileType::Soil
+                                | TileType::Sand
+                                | TileType::Plant(_)
+                                | TileType::Tree(_)
+                        ),
+                        "tile {x},{y}"
+                    ),
                 }
             }
         }
     }
-}
 
-fn cell_altitude(noise: &FastNoise, tile_x: usize, tile_y: usize, x: usize, y: usize) -> u32 {
-    let lat = noise_lat(tile_y, y);
-    let lon = noise_lon(tile_x, x);
-    let sphere_coords = sphere_vertex(100.0, Degrees::new(lat), Degrees::new(lon));
-    let noise_height = noise.get_noise3d(sphere_coords.0, sphere_coords.1, sphere_coords.2);
-    noise_to_planet_height(noise_height)
-}
+    #[test]
+    fn test_chunks_match_generated_region() {
+        setup_test_planet();
+        let location = PlanetLocation::new(IVec2::new(9, 3));
+        spawn_playable_region(location);
+        populate_region(location);
+
+        let world = location.to_world() + IVec2::new(3, 5);
+        let elevation = elevation_at(None, world).unwrap();
 
-fn pick_material(materials: &[usize], noise: f32) -> usize {
-    let noise_normalized = (noise + 1.0) / 2.0;
-    let n = materials.len() as f32 / 1.0;
-    materials[(noise_normalized * n) as usize]
+        // Chunks built on their own, like the ones next to a region, match the region
+        let regions = REGIONS.read();
+        let region = regions.get_region(location.to_region_index()).unwrap();
+        assert_eq!(elevation, region.elevation[mapidx(3, 5)]);
+
+        let region_base = ChunkLocation::from(location.to_world());
+        for chunk_location in AllChunksIterator::new() {
+            let chunk = build_chunk(region_base + chunk_location);
+            let tiles = ChunkIterator::new(chunk_location)
+                .map(|loc| {
+                    (region.tiles[loc.to_tile_index()], region.elevation[loc.to_tile_index()])
+                })
+                .collect::<Vec<_>>();
+            let chunk_tiles = chunk.tiles.into_iter().zip(chunk.elevation).collect::<Vec<_>>();
+            assert!(chunk_tiles == tiles, "chunk {chunk_location:?} differs");
+        }
+    }
+
+    #[test]
+    fn test_rebuilt_region_keeps_saved_chunks() {
+        setup_test_planet();
+        let slot = SaveSlot::new("region builder resume");
+        let location = PlanetLocation::new(IVec2::new(12, 4));
+        build_region(test_planet(), location, slot.clone());
+
+        let chunk_id = ChunkLocation::from(location.to_world());
+        let mut chunk = load_chunk(&slot, chunk_id).unwrap();
+        let edited =
+            if chunk.tiles[0] == TileType::Wall { TileType::Floor } else { TileType::Wall };
+        chunk.tiles[0] = edited;
+        save_chunk(&slot, &chunk);
+
+        // Resuming the embark builds the region again
+        build_region(test_planet(), location, slot.clone());
+        assert_eq!(load_chunk(&slot, chunk_id).unwrap().tiles[0], edited);
+    }
+
+    #[test]
+    fn test_chunks_saved_without_elevation_get_the_terrain() {
+        setup_test_planet();
+        let slot = SaveSlot::new("chunk elevation");
+        let chunk_id = ChunkLocation::from(PlanetLocation::new(IVec2::new(14, 6)).to_world());
+        let mut chunk = build_chunk(chunk_id);
+        let elevation = std::mem::take(&mut chunk.elevation);
+        save_chunk(&slot, &chunk);
+
+        assert_eq!(load_chunk(&slot, chunk_id).unwrap().elevation, elevation);
+    }
 }
@@ -0,0 +1,51 @@
+use crate::prelude::*;
+use bracket_random::prelude::RandomNumberGenerator;
+
+/// The generation steps rolling for a tile. Each step gets its own random stream, so adding
+/// rolls to one step doesn't change the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileRoll {
+    Soil = 1,
+    Plant = 2,
+    Tree = 3,
+}
+
+/// A random number generator for one world-space tile and generation step. Seeding every
+/// tile on its own instead of drawing from a stream per region or chunk makes the result
+/// independent of the order tiles are generated in, so regions and chunks generated
+/// separately line up at their edges.
+pub fn tile_rng(seed: u64, world: IVec2, roll: TileRoll) -> RandomNumberGenerator {
+    let x = world.x.rem_euclid(PLANET_TILE_WIDTH) as u32 as u64;
+    let y = world.y as u32 as u64;
+    RandomNumberGenerator::seeded(mix(seed ^ mix(((x << 32) | y) ^ mix(roll as u64))))
+}
+
+/// The SplitMix64 finalizer, spreads nearby inputs over the whole output range.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rolls(world: IVec2, roll: TileRoll) -> Vec<i32> {
+        let mut rng = tile_rng(42, world, roll);
+        (0..8).map(|_| rng.roll_dice(1, 100)).collect()
+    }
+
+    #[test]
+    fn test_tile_rng() {
+        let world = IVec2::new(REGION_WIDTH as i32 - 1, 17);
+        assert_eq!(rolls(world, TileRoll::Plant), rolls(world, TileRoll::Plant));
+        assert_ne!(rolls(world, TileRoll::Plant), rolls(world, TileRoll::Tree));
+        assert_ne!(rolls(world, TileRoll::Plant), rolls(world + IVec2::X, TileRoll::Plant));
+
+        // Both copies of a tile across the east/west edge roll the same
+        let east = IVec2::new(PLANET_TILE_WIDTH - 1, 17);
+        assert_eq!(rolls(east, TileRoll::Soil), rolls(IVec2::new(-1, 17), TileRoll::Soil));
+    }
+}
@@ -22,8 +22,45 @@ impl PlanetData {
 }
 
 pub fn set_global_planet(planet: Planet) {
-    let planet_copy = planet.clone();
-    PLANET_STORE.write().planet = Some(planet);
-    PLANET_STORE.write().height_noise = Some(planet_copy.get_height_noise());
-    PLANET_STORE.write().material_noise = Some(planet_copy.get_material_noise());
+    let height_noise = planet.get_height_noise();
+    let material_noise = planet.get_material_noise();
+
+    // A single lock, so chunks populating meanwhile never see a mix of two planets
+    let mut store = PLANET_STORE.write();
+    store.planet = Some(planet);
+    store.height_noise = Some(height_noise);
+    store.material_noise = Some(material_noise);
+}
+
+/// A flat planet covered by the first biome, for tests.
+#[cfg(test)]
+pub fn test_planet() -> Planet {
+    let landblock = Landblock {
+        height: 0,
+        variance: 0,
+        btype: BiomeType::None,
+        rainfall_mm: 0,
+        biome_idx: 0,
+        temperature_c: 0.0,
+        air_pressure_kpa: 0.0,
+        prevailing_wind: crate::simulation::planet::Direction::None,
+        neighbors: planet_neighbors_four_way(0),
+    };
+    Planet {
+        noise_seed: 7,
+        landblocks: vec![landblock; WORLD_TILES_COUNT],
+        ..Default::default()
+    }
+}
+
+/// Makes [`test_planet`] the global planet, and loads the raws and task pools chunks are
+/// populated with. Tests share these globals, so only the first call sets them up.
+#[cfg(test)]
+pub fn setup_test_planet() {
+    static SETUP: std::sync::Once = std::sync::Once::new();
+    SETUP.call_once(|| {
+        bevy::tasks::AsyncComputeTaskPool::init(bevy::tasks::TaskPool::new);
+        load_raws().unwrap();
+        set_global_planet(test_planet());
+    });
 }
@@ -12,6 +12,11 @@ pub struct Planet {
     pub landblocks: Vec<Landblock>,
 }
 
+impl SavePayload for Planet {
+    const PAYLOAD: PayloadType = PayloadType::Planet;
+    const VERSION: u32 = 1;
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Landblock {
     pub height: u32,
@@ -56,16 +61,15 @@ impl Planet {
     }
 }
 
-pub fn save_planet(planet: Planet) {
+pub fn save_planet(slot: &SaveSlot, planet: Planet) {
     println!("Saving planet");
-    if let Err(err) = save_data(world_save_location("world.dat"), planet) {
-        println!("Error saving world: {err:?}");
+    if let Err(err) =
+        save_data(slot.planet_path(), planet).and_then(|_| save_storage().flush())
+    {
+        println!("Error saving world: {}", err.report());
     }
 }
 
-pub fn load_planet() -> Planet {
-    match load_data::<Planet>(world_save_location("world.dat")) {
-        Ok(planet) => planet,
-        Err(err) => panic!("Error loading world: {err:?}"),
-    }
+pub fn load_planet(slot: &SaveSlot) -> Result<Planet, IOError> {
+    load_data::<Planet>(slot.planet_path()).map_err(|err| err.in_world(slot))
 }
@@ -27,13 +27,26 @@ impl Regions {
 
 //////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+/// Where a region is in its lifecycle. Regions are queued as `NotLoaded`, generated on the
+/// task pool, saved before they are evicted and read back from the save when needed again.
+#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum RegionStatus {
+    /// Queued, or never requested.
     #[default]
     NotLoaded,
-    CreatingTiles,
-    CreatedTiles,
-    Done,
+    /// Chunks of the region are being populated.
+    Generating,
+    /// The tiles are in memory and haven't been saved.
+    Ready,
+    /// The tiles are in memory and match the saved copy.
+    Persisted,
+    /// The tiles were dropped from memory and only live in the save.
+    Evicted,
+}
+
+impl RegionStatus {
+    /// True when the region's tiles are in memory and can be queried.
+    pub fn is_ready(&self) -> bool { matches!(self, Self::Ready | Self::Persisted) }
 }
 
 #[derive(Default, Debug)]
@@ -41,6 +54,8 @@ pub struct Region {
     pub material: Vec<usize>,
     pub status: RegionStatus,
     pub tiles: Vec<TileType>,
+    /// Height of the ground under each tile, on the same scale as [`Planet::water_height`].
+    pub elevation: Vec<u32>,
     pub chunks_loaded: Vec<bool>,
     pub location: PlanetLocation,
 }
@@ -53,9 +68,26 @@ impl Region {
             material: vec![0; REGION_TILES_COUNT],
             chunks_loaded: vec![false; CHUNKS_PER_REGION],
             tiles: vec![TileType::Floor; REGION_TILES_COUNT],
+            elevation: vec![0; REGION_TILES_COUNT],
         }
     }
 
+    /// Bytes held by the tiles, materials and elevation.
+    pub fn heap_size(&self) -> usize {
+        self.tiles.capacity() * std::mem::size_of::<TileType>()
+            + self.material.capacity() * std::mem::size_of::<usize>()
+            + self.elevation.capacity() * std::mem::size_of::<u32>()
+            + self.chunks_loaded.capacity()
+    }
+
+    /// Drops the tiles from memory, they must have been saved with [`persist_region`] first.
+    pub fn evict(&mut self) {
+        self.tiles = Vec::new();
+        self.material = Vec::new();
+        self.elevation = Vec::new();
+        self.status = RegionStatus::Evicted;
+    }
+
     pub fn is_floor(&self, idx: usize) -> bool {
         matches!(self.tiles[idx], TileType::Floor { .. })
     }
@@ -8,9 +8,13 @@ pub const REGION_WIDTH: usize = 256;
 pub const REGION_HEIGHT: usize = 256;
 pub const REGION_TILES_COUNT: usize = REGION_WIDTH * REGION_HEIGHT;
 
+// Planet width in tiles, where the east/west edges wrap around
+pub const PLANET_TILE_WIDTH: i32 = (WORLD_WIDTH * REGION_WIDTH) as i32;
+
 // Terrain Chunks
 pub const CHUNK_SIZE: usize = 32;
 pub const CHUNK_SIZE_U32: u32 = CHUNK_SIZE as u32;
+pub const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
 pub const CHUNK_SIZE_U: usize = CHUNK_SIZE;
 pub const CHUNK_WIDTH: usize = REGION_WIDTH / CHUNK_SIZE;
 pub const CHUNK_HEIGHT: usize = REGION_HEIGHT / CHUNK_SIZE;
@@ -1,19 +1,84 @@
 use crate::prelude::*;
 
-// A component tagging an entity as a chunk.
-#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+/// The tile data of a single chunk, as it is stored on disk and handed between tasks.
+/// Loaded chunks live in the [`ChunkMap`]s instead, see [`ChunkHandle`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chunk {
+    #[serde(with = "paletted_tiles")]
     pub tiles: Vec<TileType>,
+    /// Height of the ground under each tile, see [`Region::elevation`].
+    #[serde(with = "paletted_tiles")]
+    pub elevation: Vec<u32>,
     pub region: PlanetLocation,
     pub location: ChunkLocation,
+    /// The [`Persistent`] entities standing in the chunk when it was unloaded.
+    pub entities: Vec<SavedEntity>,
+}
+
+impl SavePayload for Chunk {
+    const PAYLOAD: PayloadType = PayloadType::Chunk;
+    const VERSION: u32 = 5;
 }
 
 impl Chunk {
     pub fn new(region: PlanetLocation, location: ChunkLocation) -> Self {
-        Self { location, region, tiles: vec![TileType::Floor; TILES_PER_CHUNK] }
+        Self {
+            location,
+            region,
+            tiles: vec![TileType::Floor; TILES_PER_CHUNK],
+            elevation: vec![0; TILES_PER_CHUNK],
+            entities: Vec::new(),
+        }
     }
 
     pub fn empty(region: PlanetLocation, location: ChunkLocation) -> Self {
-        Self { tiles: Vec::with_capacity(0), location, region }
+        Self {
+            tiles: Vec::with_capacity(0),
+            elevation: Vec::with_capacity(0),
+            location,
+            region,
+            entities: Vec::new(),
+        }
+    }
+
+    /// Copies the tiles and elevation of a loaded chunk's buffers.
+    pub fn from_buffer(
+        region: PlanetLocation,
+        location: ChunkLocation,
+        buffer: &ChunkBuffer<TileType, ChunkShape>,
+        elevation: &ChunkBuffer<u32, ChunkShape>,
+        entities: Vec<SavedEntity>,
+    ) -> Self {
+        Self {
+            tiles: buffer.to_vec(),
+            elevation: elevation.to_vec(),
+            location,
+            region,
+            entities,
+        }
+    }
+
+    /// Moves the tiles and the elevation into buffers that can be inserted in the
+    /// [`ChunkMap`]s.
+    pub fn into_buffers(
+        self,
+    ) -> (ChunkBuffer<TileType, ChunkShape>, ChunkBuffer<u32, ChunkShape>) {
+        (
+            ChunkBuffer::from_vec(ChunkShape {}, self.tiles),
+            ChunkBuffer::from_vec(ChunkShape {}, self.elevation),
+        )
     }
+
+    /// Returns the handle tagging the chunk's entity.
+    pub fn handle(&self) -> ChunkHandle {
+        ChunkHandle { region: self.region, location: self.location }
+    }
+}
+
+// A component tagging an entity as a chunk. Its tiles and elevation are stored in the
+// [`ChunkMap`]s at `location`.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ChunkHandle {
+    pub region: PlanetLocation,
+    pub location: ChunkLocation,
 }
@@ -1,12 +1,14 @@
 use crate::prelude::*;
 use derive_more::{Add, Sub};
 
+/// World-space tile coordinates of a chunk's minimum corner. Coordinates are signed so
+/// chunks west/north of the origin (or off the planet entirely) can be addressed.
 #[derive(
     Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Add, Sub, Serialize, Deserialize,
 )]
 pub struct ChunkLocation {
-    pub x: usize,
-    pub y: usize,
+    pub x: i32,
+    pub y: i32,
 }
 
 impl ChunkLocation {
@@ -14,38 +16,51 @@ impl ChunkLocation {
     pub const ZERO: Self = Self::splat(0);
 
     #[inline(always)]
-    pub fn new(x: usize, y: usize) -> Self { Self { x, y } }
+    pub fn new(x: i32, y: i32) -> Self { Self { x, y } }
 
     /// Creates a vector with all elements set to `v`.
     #[inline]
-    pub const fn splat(v: usize) -> Self { Self { x: v, y: v } }
+    pub const fn splat(v: i32) -> Self { Self { x: v, y: v } }
 
+    /// Converts a region-local location to a region tile index.
     #[inline]
-    pub fn to_tile_index(&self) -> usize { mapidx(self.x, self.y) }
+    pub fn to_tile_index(&self) -> usize { mapidx(self.x as usize, self.y as usize) }
 
     #[inline]
     pub fn to_planet_location(&self) -> PlanetLocation {
         PlanetLocation(IVec2::new(
-            (self.x / REGION_WIDTH) as i32,
-            (self.y / REGION_HEIGHT) as i32,
+            self.x.div_euclid(REGION_WIDTH as i32),
+            self.y.div_euclid(REGION_HEIGHT as i32),
         ))
     }
 
+    /// Wraps the location around the east/west edge of the planet, matching
+    /// [`planet_neighbors_four_way`]. The north/south axis does not wrap.
     #[inline]
-    pub fn as_point(&self) -> Point { Point::new(self.x as i32, self.y as i32) }
+    pub fn wrapped(&self) -> Self {
+        Self { x: self.x.rem_euclid(PLANET_TILE_WIDTH), y: self.y }
+    }
+
+    /// Returns the world-space origin of this chunk on the copy of the planet closest to
+    /// `x`, so chunks across the east/west edge are placed next to the viewer.
+    #[inline]
+    pub fn nearest_to(&self, x: i32) -> IVec2 {
+        let offset = (x - self.x + PLANET_TILE_WIDTH / 2).div_euclid(PLANET_TILE_WIDTH);
+        IVec2::new(self.x + offset * PLANET_TILE_WIDTH, self.y)
+    }
+
+    #[inline]
+    pub fn as_point(&self) -> Point { Point::new(self.x, self.y) }
 
     /// Casts all elements of `self` to `f32`.
     #[inline]
     pub fn as_vec2(&self) -> crate::Vec2 { crate::Vec2::new(self.x as f32, self.y as f32) }
 
-    /// Casts all elements of `self` to `f32`.
     #[inline]
-    pub fn as_ivec2(&self) -> crate::IVec2 { crate::IVec2::new(self.x as i32, self.y as i32) }
+    pub fn as_ivec2(&self) -> crate::IVec2 { crate::IVec2::new(self.x, self.y) }
 
     pub fn chunk_key_from(&self, x: i32, y: i32) -> ChunkLocation {
-        let new_x = self.x + x as usize;
-        let new_y = self.y + y as usize;
-        ChunkLocation::new(new_x, new_y)
+        ChunkLocation::new(self.x + x, self.y + y)
     }
 }
 
@@ -54,9 +69,36 @@ impl From<ChunkLocation> for Vec2 {
 }
 
 impl From<Vec2> for ChunkLocation {
-    fn from(vec: Vec2) -> Self { Self { x: vec.x as usize, y: vec.y as usize } }
+    fn from(vec: Vec2) -> Self { Self { x: vec.x as i32, y: vec.y as i32 } }
 }
 
 impl From<IVec2> for ChunkLocation {
-    fn from(vec: IVec2) -> Self { Self { x: vec.x as usize, y: vec.y as usize } }
+    fn from(vec: IVec2) -> Self { Self { x: vec.x, y: vec.y } }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_negative_planet_location() {
+        let loc = ChunkLocation::new(-CHUNK_SIZE_I32, -CHUNK_SIZE_I32);
+        assert_eq!(loc.to_planet_location(), PlanetLocation::new(IVec2::new(-1, -1)));
+    }
+
+    #[test]
+    fn test_wrap_east_west() {
+        let west = ChunkLocation::new(-CHUNK_SIZE_I32, 64);
+        assert_eq!(west.wrapped(), ChunkLocation::new(PLANET_TILE_WIDTH - CHUNK_SIZE_I32, 64));
+
+        let east = ChunkLocation::new(PLANET_TILE_WIDTH, 64);
+        assert_eq!(east.wrapped(), ChunkLocation::new(0, 64));
+    }
+
+    #[test]
+    fn test_nearest_to() {
+        let loc = ChunkLocation::new(PLANET_TILE_WIDTH - CHUNK_SIZE_I32, 0);
+        assert_eq!(loc.nearest_to(0), IVec2::new(-CHUNK_SIZE_I32, 0));
+        assert_eq!(loc.nearest_to(PLANET_TILE_WIDTH - 1), loc.as_ivec2());
+    }
 }
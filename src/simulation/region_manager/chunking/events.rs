@@ -0,0 +1,27 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone)]
+/// A request to change tiles of loaded chunks. Positions are world-space tile coordinates.
+/// Edits targeting chunks that are not loaded are dropped.
+pub enum TileEdit {
+    /// Sets a single tile.
+    Set(IVec2, TileType),
+    /// Fills the rectangle starting at `min` with the given size.
+    Fill { min: IVec2, size: UVec2, tile: TileType },
+    /// Sets many tiles at once.
+    Batch(Vec<(IVec2, TileType)>),
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Sent by [`ChunkLoadingSystem::ProcessChunkLoads`] once a chunk's tiles are in the
+/// [`ChunkMap`] and its entity is tagged with a [`ChunkHandle`].
+pub struct ChunkLoaded(pub ChunkLocation, pub Entity);
+
+#[derive(Debug, Clone, Copy)]
+/// Sent by [`ChunkLoadingSystem::DestroyChunks`] once a chunk's tiles were removed from the
+/// [`ChunkMap`]. Its entity is despawned once any pending save completes.
+pub struct ChunkUnloaded(pub ChunkLocation);
+
+#[derive(Debug, Clone, Copy)]
+/// Sent once per chunk whenever a [`TileEdit`] changed its tiles.
+pub struct ChunkModified(pub ChunkLocation);
@@ -34,8 +34,8 @@ impl Iterator for AllChunksIterator {
             return None;
         }
 
-        let result =
-            self.chunk_base + ChunkLocation { x: self.x * CHUNK_SIZE, y: self.y * CHUNK_SIZE };
+        let result = self.chunk_base
+            + ChunkLocation::new((self.x * CHUNK_SIZE) as i32, (self.y * CHUNK_SIZE) as i32);
         self.x += 1;
         if self.x == CHUNK_WIDTH {
             self.x = 0;
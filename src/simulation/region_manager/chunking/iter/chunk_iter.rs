@@ -1,8 +1,8 @@
 use crate::prelude::*;
 
 /// Iterates all tiles in a CHUNK_SIZE^3 chunk, based on the ChunkLocation
-/// as the base position. Each returned location is a region-wide location,
-/// not a chunk-wide location.
+/// as the base position. Each returned location is in the same space as the
+/// base (region-wide or world-wide), not a chunk-wide location.
 pub struct ChunkIterator {
     done: bool,
     chunk_base: ChunkLocation,
@@ -25,10 +25,10 @@ impl Iterator for ChunkIterator {
 
         let result = self.current;
         self.current.x += 1;
-        if self.current.x == self.chunk_base.x + CHUNK_SIZE {
+        if self.current.x == self.chunk_base.x + CHUNK_SIZE_I32 {
             self.current.x = self.chunk_base.x;
             self.current.y += 1;
-            if self.current.y == self.chunk_base.y + CHUNK_SIZE {
+            if self.current.y == self.chunk_base.y + CHUNK_SIZE_I32 {
                 self.done = true;
             }
         }
@@ -2,25 +2,39 @@ use crate::prelude::*;
 
 mod bounds;
 mod chunks;
+mod events;
 mod iter;
+mod queue;
+mod region_file;
 mod resources;
 mod storage;
 mod systems;
+mod viewer;
 
 pub use bounds::*;
 pub use chunks::*;
+pub use events::*;
 pub use iter::*;
+pub use queue::*;
+pub use region_file::*;
 pub use resources::*;
 pub use storage::*;
 pub use systems::*;
+pub use viewer::*;
 
 pub struct ChunkingPlugin;
 impl Plugin for ChunkingPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<ChunkCommandQueue>()
+            .init_resource::<Autosave>()
             .init_resource::<DirtyChunks>()
+            .init_resource::<ModifiedChunks>()
+            .add_event::<TileEdit>()
+            .add_event::<ChunkModified>()
+            .add_event::<ChunkLoaded>()
+            .add_event::<ChunkUnloaded>()
             .init_resource::<ChunkEntities>()
-            .insert_resource::<ChunkLoadRadius>(ChunkLoadRadius { horizontal: 4, vertical: 4 })
+            .init_resource::<ChunkViewerRefs>()
             .insert_resource::<CurrentLocalPlayerChunk>(CurrentLocalPlayerChunk::ZERO)
             .add_stage_after(
                 CoreStage::Update,
@@ -30,8 +44,6 @@ impl Plugin for ChunkingPlugin {
                         ConditionSet::new()
                             .label(ChunkLoadingSystem::UpdateViewChunks)
                             .run_in_state(GameState::InGame)
-                            .run_if_resource_exists::<ChunkLoadRadius>()
-                            .run_if_resource_exists::<CurrentLocalPlayerChunk>()
                             .with_system(update_view_chunks)
                             .into(),
                     )
@@ -42,15 +54,50 @@ impl Plugin for ChunkingPlugin {
                             .run_in_state(GameState::InGame)
                             .with_system(create_chunks)
                             .into(),
+                    )
+                    .with_system_set(
+                        ConditionSet::new()
+                            .label(ChunkLoadingSystem::ProcessChunkLoads)
+                            .after(ChunkLoadingSystem::CreateChunks)
+                            .run_in_state(GameState::InGame)
+                            .with_system(process_chunk_load)
+                            .into(),
+                    )
+                    .with_system_set(
+                        ConditionSet::new()
+                            .label(ChunkLoadingSystem::ApplyTileEdits)
+                            .after(ChunkLoadingSystem::ProcessChunkLoads)
+                            .run_in_state(GameState::InGame)
+                            .with_system(apply_tile_edits)
+                            .into(),
                     ),
             )
             .add_system_set_to_stage(
                 CoreStage::Last,
                 ConditionSet::new()
+                    .label(ChunkLoadingSystem::Autosave)
+                    .before(ChunkLoadingSystem::DestroyChunks)
+                    .run_in_state(GameState::InGame)
+                    .with_system(autosave)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                CoreStage::Last,
+                ConditionSet::new()
+                    .label(ChunkLoadingSystem::DestroyChunks)
                     .run_in_state(GameState::InGame)
                     .with_system(destroy_chunks)
                     .into(),
             )
+            .add_system_set_to_stage(
+                CoreStage::Last,
+                ConditionSet::new()
+                    .label(ChunkLoadingSystem::ProcessChunkSaves)
+                    .after(ChunkLoadingSystem::DestroyChunks)
+                    .run_in_state(GameState::InGame)
+                    .with_system(process_chunk_save)
+                    .into(),
+            )
             .add_system_set_to_stage(
                 CoreStage::Last,
                 ConditionSet::new()
@@ -58,6 +105,15 @@ impl Plugin for ChunkingPlugin {
                     .run_in_state(GameState::InGame)
                     .with_system(clear_dirty_chunks)
                     .into(),
+            )
+            .add_system_set_to_stage(
+                CoreStage::Last,
+                ConditionSet::new()
+                    .label(ChunkLoadingSystem::FlushSaves)
+                    .after(ChunkLoadingSystem::ProcessChunkSaves)
+                    .run_in_state(GameState::InGame)
+                    .with_system(flush_saves_on_exit)
+                    .into(),
             );
     }
 }
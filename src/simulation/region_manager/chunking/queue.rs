@@ -0,0 +1,124 @@
+use crate::prelude::*;
+use bevy::utils::HashSet;
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+/// A chunk waiting to be created, ordered by its priority. Lower values are created first.
+#[derive(Debug, Clone, Copy)]
+struct QueuedChunk {
+    priority: i32,
+    location: ChunkLocation,
+}
+
+impl PartialEq for QueuedChunk {
+    fn eq(&self, other: &Self) -> bool { self.priority == other.priority }
+}
+
+impl Eq for QueuedChunk {}
+
+impl PartialOrd for QueuedChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for QueuedChunk {
+    // Reversed so the `BinaryHeap` max-heap pops the lowest priority value first.
+    fn cmp(&self, other: &Self) -> Ordering { other.priority.cmp(&self.priority) }
+}
+
+/// A priority queue of chunk locations, popping the lowest priority value first.
+/// Each location is queued at most once.
+#[derive(Debug, Default)]
+pub struct ChunkPriorityQueue {
+    heap: BinaryHeap<QueuedChunk>,
+    queued: HashSet<ChunkLocation>,
+}
+
+impl ChunkPriorityQueue {
+    pub fn new() -> Self { Self::default() }
+
+    /// Queues a chunk location. Returns false if the location was already queued.
+    pub fn push(&mut self, location: ChunkLocation, priority: i32) -> bool {
+        if !self.queued.insert(location) {
+            return false;
+        }
+
+        self.heap.push(QueuedChunk { priority, location });
+        true
+    }
+
+    /// Removes and returns the location with the lowest priority value.
+    pub fn pop(&mut self) -> Option<ChunkLocation> {
+        let next = self.heap.pop()?;
+        self.queued.remove(&next.location);
+        Some(next.location)
+    }
+
+    pub fn contains(&self, location: &ChunkLocation) -> bool { self.queued.contains(location) }
+
+    /// Keeps only the locations matching the predicate.
+    pub fn retain(&mut self, mut f: impl FnMut(&ChunkLocation) -> bool) {
+        let mut heap = std::mem::take(&mut self.heap).into_vec();
+        heap.retain(|queued| f(&queued.location));
+        self.queued = heap.iter().map(|queued| queued.location).collect();
+        self.heap = heap.into();
+    }
+
+    /// Recomputes the priority of every queued location.
+    pub fn reprioritize(&mut self, mut priority: impl FnMut(&ChunkLocation) -> i32) {
+        let mut heap = std::mem::take(&mut self.heap).into_vec();
+        heap.iter_mut().for_each(|queued| queued.priority = priority(&queued.location));
+        self.heap = heap.into();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChunkLocation> { self.queued.iter() }
+
+    pub fn len(&self) -> usize { self.heap.len() }
+
+    pub fn is_empty(&self) -> bool { self.heap.is_empty() }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.queued.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pop_order() {
+        let mut queue = ChunkPriorityQueue::new();
+        queue.push(ChunkLocation::new(64, 0), 2);
+        queue.push(ChunkLocation::new(0, 0), 0);
+        queue.push(ChunkLocation::new(32, 0), 1);
+
+        assert_eq!(queue.pop(), Some(ChunkLocation::new(0, 0)));
+        assert_eq!(queue.pop(), Some(ChunkLocation::new(32, 0)));
+        assert_eq!(queue.pop(), Some(ChunkLocation::new(64, 0)));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_no_duplicates() {
+        let mut queue = ChunkPriorityQueue::new();
+        assert!(queue.push(ChunkLocation::ZERO, 0));
+        assert!(!queue.push(ChunkLocation::ZERO, 5));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_reprioritize_and_retain() {
+        let mut queue = ChunkPriorityQueue::new();
+        queue.push(ChunkLocation::new(0, 0), 0);
+        queue.push(ChunkLocation::new(32, 0), 1);
+        queue.push(ChunkLocation::new(64, 0), 2);
+
+        queue.reprioritize(|loc| -(loc.x as i32));
+        queue.retain(|loc| loc.x != 32);
+
+        assert!(!queue.contains(&ChunkLocation::new(32, 0)));
+        assert_eq!(queue.pop(), Some(ChunkLocation::new(64, 0)));
+        assert_eq!(queue.pop(), Some(ChunkLocation::new(0, 0)));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,475 @@
+use crate::prelude::*;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+
+/// Allocation unit of chunk data within a region file.
+const SECTOR_SIZE: u64 = 512;
+/// Bytes per offset table entry: first sector, sector count and data length.
+const ENTRY_SIZE: usize = 12;
+/// Starts region files whose offset table also lists the previous data of each chunk.
+const REGION_FILE_MAGIC: &[u8; 4] = b"RGN2";
+/// Offset table entries, the current data of each chunk followed by its previous data.
+const TABLE_ENTRIES: usize = CHUNKS_PER_REGION * 2;
+/// Sectors reserved at the start of a region file for the magic and offset table.
+const TABLE_SECTORS: u32 =
+    (((REGION_FILE_MAGIC.len() + TABLE_ENTRIES * ENTRY_SIZE) as u64 + SECTOR_SIZE - 1)
+        / SECTOR_SIZE) as u32;
+
+lazy_static! {
+    /// Chunks are saved and loaded from the task pools. Only one of them may touch a region
+    /// file at a time, so no chunk is lost between reading the file and writing it back.
+    static ref REGION_FILE_LOCKS: Mutex<HashMap<String, Arc<Mutex<()>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// The lock of the region file stored at `filename`. Chunks of other regions are read and
+/// written in parallel.
+fn region_file_lock(filename: &str) -> Arc<Mutex<()>> {
+    REGION_FILE_LOCKS.lock().entry(filename.to_string()).or_default().clone()
+}
+
+/// Location of a chunk's data within its region file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RegionFileEntry {
+    sector: u32,
+    sectors: u32,
+    length: u32,
+}
+
+impl RegionFileEntry {
+    fn is_empty(&self) -> bool { self.sectors == 0 }
+
+    fn read(bytes: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+        Self { sector: word(0), sectors: word(1), length: word(2) }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..4].copy_from_slice(&self.sector.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.sectors.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.length.to_le_bytes());
+        bytes
+    }
+}
+
+/// A file holding the saved chunks of a single region. It starts with an offset table
+/// with two entries for each of the [`CHUNKS_PER_REGION`] chunks, followed by the compressed
+/// chunk data in fixed size sectors. The second entry keeps the data a chunk held before its
+/// last write, as a backup should the current data be damaged. Sectors freed by chunks that
+/// moved are reused.
+///
+/// The file is edited in memory, then written back to the [`SaveStorage`] as a whole.
+pub struct RegionFile {
+    bytes: Vec<u8>,
+    entries: Vec<RegionFileEntry>,
+}
+
+impl Default for RegionFile {
+    fn default() -> Self { Self::from_bytes(Vec::new()) }
+}
+
+impl RegionFile {
+    /// Parses a region file, padding the offset table of a new or truncated one. Files written
+    /// before backups were kept are upgraded.
+    pub fn from_bytes(mut bytes: Vec<u8>) -> Self {
+        if !bytes.is_empty() && !bytes.starts_with(REGION_FILE_MAGIC) {
+            return Self::upgrade(&bytes);
+        }
+
+        let table_size = (TABLE_SECTORS as u64 * SECTOR_SIZE) as usize;
+        if bytes.len() < table_size {
+            bytes.resize(table_size, 0);
+        }
+        bytes[..REGION_FILE_MAGIC.len()].copy_from_slice(REGION_FILE_MAGIC);
+
+        let table = REGION_FILE_MAGIC.len();
+        let entries = bytes[table..table + TABLE_ENTRIES * ENTRY_SIZE]
+            .chunks_exact(ENTRY_SIZE)
+            .map(RegionFileEntry::read)
+            .collect();
+        Self { bytes, entries }
+    }
+
+    /// Moves the chunks of a file without backups, whose offset table only held the current
+    /// entries, into a new file. Chunks whose data lies past the end of the file are dropped.
+    fn upgrade(bytes: &[u8]) -> Self {
+        let mut file = Self::default();
+        let table_size = CHUNKS_PER_REGION * ENTRY_SIZE;
+        let legacy = match bytes.get(..table_size) {
+            Some(table) => table,
+            None => return file,
+        };
+
+        for (index, entry) in
+            legacy.chunks_exact(ENTRY_SIZE).map(RegionFileEntry::read).enumerate()
+        {
+            if entry.is_empty() {
+                continue;
+            }
+
+            let start = (entry.sector as u64 * SECTOR_SIZE) as usize;
+            match bytes.get(start..start + entry.length as usize) {
+                Some(data) => file.write_entry(index, data),
+                None => println!("Dropping chunk {index} past the end of its region file"),
+            }
+        }
+
+        file
+    }
+
+    /// Loads the region file stored at `key`, or an empty one if there is none.
+    pub fn load(key: &str) -> Result<Self, IOError> {
+        match save_storage().read(key) {
+            Ok(bytes) => Ok(Self::from_bytes(bytes)),
+            Err(IOError::SaveFileDoesNotExist) => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, key: &str) -> Result<(), IOError> {
+        save_storage().write(key, &self.bytes)
+    }
+
+    /// Returns true if the file holds data for the chunk.
+    pub fn contains(&self, chunk_id: ChunkLocation) -> bool {
+        !self.entries[Self::entry_index(chunk_id)].is_empty()
+    }
+
+    /// Reads the data of a chunk, or `None` if it was never written.
+    pub fn read(&self, chunk_id: ChunkLocation) -> Result<Option<Vec<u8>>, IOError> {
+        self.read_entry(self.entries[Self::entry_index(chunk_id)])
+    }
+
+    /// Reads the data a chunk held before it was last written, or `None` if it was only
+    /// written once.
+    pub fn read_backup(&self, chunk_id: ChunkLocation) -> Result<Option<Vec<u8>>, IOError> {
+        self.read_entry(self.entries[CHUNKS_PER_REGION + Self::entry_index(chunk_id)])
+    }
+
+    fn read_entry(&self, entry: RegionFileEntry) -> Result<Option<Vec<u8>>, IOError> {
+        if entry.is_empty() {
+            return Ok(None);
+        }
+
+        let start = (entry.sector as u64 * SECTOR_SIZE) as usize;
+        match self.bytes.get(start..start + entry.length as usize) {
+            Some(data) => Ok(Some(data.to_vec())),
+            None => Err(IOError::SaveFileCorrupted),
+        }
+    }
+
+    /// Writes the data of a chunk. The data never overwrites the sectors it replaces, they're
+    /// kept as the chunk's backup until its next write.
+    pub fn write(&mut self, chunk_id: ChunkLocation, data: &[u8]) {
+        self.write_entry(Self::entry_index(chunk_id), data);
+    }
+
+    fn write_entry(&mut self, index: usize, data: &[u8]) {
+        let sectors = ((data.len() as u64 + SECTOR_SIZE - 1) / SECTOR_SIZE) as u32;
+        let entry = RegionFileEntry {
+            sector: find_free_sectors(&self.entries, sectors),
+            sectors,
+            length: data.len() as u32,
+        };
+
+        // Pad the data so the file always ends on a sector boundary
+        let start = (entry.sector as u64 * SECTOR_SIZE) as usize;
+        let end = start + (sectors as u64 * SECTOR_SIZE) as usize;
+        if self.bytes.len() < end {
+            self.bytes.resize(end, 0);
+        }
+        self.bytes[start..start + data.len()].copy_from_slice(data);
+        self.bytes[start + data.len()..end].fill(0);
+
+        // The replaced data becomes the backup, freeing the sectors of the previous backup
+        let previous = self.entries[index];
+        if !previous.is_empty() {
+            self.set_entry(CHUNKS_PER_REGION + index, previous);
+        }
+        self.set_entry(index, entry);
+    }
+
+    fn set_entry(&mut self, index: usize, entry: RegionFileEntry) {
+        let offset = REGION_FILE_MAGIC.len() + index * ENTRY_SIZE;
+        self.bytes[offset..offset + ENTRY_SIZE].copy_from_slice(&entry.to_bytes());
+        self.entries[index] = entry;
+    }
+
+    /// Every chunk the file holds data for, given the region it stores.
+    pub fn chunks(&self, region: PlanetLocation) -> Vec<ChunkLocation> {
+        let origin = region.to_world();
+        self.entries[..CHUNKS_PER_REGION]
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.is_empty())
+            .map(|(index, _)| {
+                let x = (index % CHUNK_WIDTH * CHUNK_SIZE) as i32;
+                let y = (index / CHUNK_WIDTH * CHUNK_SIZE) as i32;
+                ChunkLocation::new(origin.x + x, origin.y + y)
+            })
+            .collect()
+    }
+
+    /// Index of a chunk in the offset table, from its position within the region.
+    fn entry_index(chunk_id: ChunkLocation) -> usize {
+        let x = chunk_id.x.rem_euclid(REGION_WIDTH as i32) as usize / CHUNK_SIZE;
+        let y = chunk_id.y.rem_euclid(REGION_HEIGHT as i32) as usize / CHUNK_SIZE;
+        (y * CHUNK_WIDTH) + x
+    }
+}
+
+/// Finds the first run of `sectors` sectors unused by every entry. The sectors of a chunk
+/// being rewritten and of its backup count as used, they're only freed once its new data is
+/// written.
+fn find_free_sectors(entries: &[RegionFileEntry], sectors: u32) -> u32 {
+    let mut used = entries
+        .iter()
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| (entry.sector, entry.sectors))
+        .collect::<Vec<_>>();
+    used.sort_unstable();
+
+    let mut free = TABLE_SECTORS;
+    for (sector, len) in used {
+        if sector >= free + sectors {
+            break;
+        }
+        free = free.max(sector + len);
+    }
+
+    free
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Chunk Data
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Returns the name of the region file storing the chunk.
+pub fn region_filename(slot: &SaveSlot, chunk_id: ChunkLocation) -> String {
+    let planet_loc = chunk_id.to_planet_location();
+    slot.chunk_path(&format!("{}_{}.region", planet_loc.x, planet_loc.y))
+}
+
+/// Returns true if data for the chunk was saved.
+pub fn has_chunk_data(slot: &SaveSlot, chunk_id: ChunkLocation) -> bool {
+    let filename = region_filename(slot, chunk_id);
+    let lock = region_file_lock(&filename);
+    let _guard = lock.lock();
+    check_file_exists(&filename)
+        && RegionFile::load(&filename).map_or(false, |f| f.contains(chunk_id))
+}
+
+/// Reads the saved data of a chunk from its region file.
+pub fn read_chunk_data(
+    slot: &SaveSlot,
+    chunk_id: ChunkLocation,
+) -> Result<Option<Vec<u8>>, IOError> {
+    let filename = region_filename(slot, chunk_id);
+    let lock = region_file_lock(&filename);
+    let _guard = lock.lock();
+    RegionFile::load(&filename)
+        .and_then(|file| file.read(chunk_id))
+        .map_err(|err| err.in_file(filename))
+}
+
+/// Reads the data the chunk held before its last save.
+pub fn read_chunk_backup(
+    slot: &SaveSlot,
+    chunk_id: ChunkLocation,
+) -> Result<Option<Vec<u8>>, IOError> {
+    let filename = region_filename(slot, chunk_id);
+    let lock = region_file_lock(&filename);
+    let _guard = lock.lock();
+    RegionFile::load(&filename)
+        .and_then(|file| file.read_backup(chunk_id))
+        .map_err(|err| err.in_file(filename))
+}
+
+/// Writes the data of a chunk into its region file.
+pub fn write_chunk_data(
+    slot: &SaveSlot,
+    chunk_id: ChunkLocation,
+    data: &[u8],
+) -> Result<(), IOError> {
+    let filename = region_filename(slot, chunk_id);
+    let lock = region_file_lock(&filename);
+    let _guard = lock.lock();
+    RegionFile::load(&filename)
+        .and_then(|mut file| {
+            file.write(chunk_id, data);
+            file.save(&filename)
+        })
+        .map_err(|err| err.in_file(filename.clone()))
+}
+
+/// Lists every chunk saved in the slot.
+pub fn saved_chunks(slot: &SaveSlot) -> Vec<ChunkLocation> {
+    let prefix = format!("{}/", slot.chunk_dir());
+
+    let mut chunks = Vec::new();
+    for key in save_storage().list(&prefix) {
+        let region = key[prefix.len()..]
+            .strip_suffix(".region")
+            .and_then(|name| name.split_once('_'))
+            .and_then(|(x, y)| Some(IVec2::new(x.parse().ok()?, y.parse().ok()?)));
+
+        let region = match region {
+            Some(region) => PlanetLocation::new(region),
+            None => continue,
+        };
+
+        let lock = region_file_lock(&key);
+        let _guard = lock.lock();
+        if let Ok(file) = RegionFile::load(&key) {
+            chunks.extend(file.chunks(region));
+        }
+    }
+
+    chunks
+}
+
+/// Packs chunks saved as individual `{x}_{y}.chunk` files into their region files, removing
+/// the old files. Returns the number of chunks moved. The files were written before saves had
+/// a header, and are upgraded from that layout. Fails on the first file that can't be read,
+/// leaving it and the files after it in place.
+pub fn migrate_chunk_files(slot: &SaveSlot) -> Result<usize, IOError> {
+    let storage = save_storage();
+    let prefix = format!("{}/", slot.chunk_dir());
+
+    let mut migrated = 0;
+    for key in storage.list(&prefix) {
+        let name = match key[prefix.len()..].strip_suffix(".chunk") {
+            Some(name) => name,
+            None => continue,
+        };
+
+        // The file name holds the location the chunk was saved at
+        let chunk_id = name
+            .split_once('_')
+            .and_then(|(x, y)| Some(ChunkLocation::new(x.parse().ok()?, y.parse().ok()?)))
+            .ok_or_else(|| IOError::SaveFileCorrupted.in_file(key.clone()))?;
+
+        let mut chunk = load_data::<Chunk>(key.clone())?;
+        chunk.location = chunk_id;
+
+        write_chunk_data(slot, chunk_id, &encode_data(&chunk)?)?;
+        let _ = storage.delete(&backup_path(&key));
+        if let Err(err) = storage.delete(&key) {
+            println!("Failed to remove chunk file {key}: {}", err.report());
+        }
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(sector: u32, sectors: u32) -> RegionFileEntry {
+        RegionFileEntry { sector, sectors, length: sectors * SECTOR_SIZE as u32 }
+    }
+
+    #[test]
+    fn test_find_free_sectors() {
+        let mut entries = vec![RegionFileEntry::default(); CHUNKS_PER_REGION];
+        assert_eq!(find_free_sectors(&entries, 2), TABLE_SECTORS);
+
+        entries[0] = entry(TABLE_SECTORS, 2);
+        entries[1] = entry(TABLE_SECTORS + 4, 1);
+
+        // Data goes to the first gap that fits, never over sectors that are still in use
+        assert_eq!(find_free_sectors(&entries, 1), TABLE_SECTORS + 2);
+        assert_eq!(find_free_sectors(&entries, 2), TABLE_SECTORS + 2);
+        assert_eq!(find_free_sectors(&entries, 3), TABLE_SECTORS + 5);
+        assert_eq!(find_free_sectors(&entries, 4), TABLE_SECTORS + 5);
+    }
+
+    #[test]
+    fn test_region_file_round_trip() {
+        let first = ChunkLocation::new(0, 0);
+        let second = ChunkLocation::new(CHUNK_SIZE_I32, 0);
+
+        let mut file = RegionFile::default();
+        file.write(first, &[1; 700]);
+        file.write(second, &[2; 10]);
+        file.write(first, &[3; 1200]);
+
+        let file = RegionFile::from_bytes(file.bytes);
+        assert_eq!(file.read(first).unwrap(), Some(vec![3; 1200]));
+        assert_eq!(file.read(second).unwrap(), Some(vec![2; 10]));
+        assert_eq!(file.read(ChunkLocation::new(0, CHUNK_SIZE_I32)).unwrap(), None);
+
+        let region = PlanetLocation::new(IVec2::new(1, 0));
+        let mut chunks = file.chunks(region);
+        chunks.sort_by_key(|chunk| chunk.x);
+        let origin = region.to_world();
+        assert_eq!(
+            chunks,
+            vec![
+                ChunkLocation::new(origin.x, origin.y),
+                ChunkLocation::new(origin.x + CHUNK_SIZE_I32, origin.y)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_region_file_backup() {
+        let chunk_id = ChunkLocation::new(CHUNK_SIZE_I32, CHUNK_SIZE_I32);
+        let mut file = RegionFile::default();
+        file.write(chunk_id, &[1; 10]);
+        assert_eq!(file.read_backup(chunk_id).unwrap(), None);
+
+        // Each write keeps the data it replaces, freeing the backup before it
+        file.write(chunk_id, &[2; 10]);
+        file.write(chunk_id, &[3; 600]);
+        let file = RegionFile::from_bytes(file.bytes);
+        assert_eq!(file.read(chunk_id).unwrap(), Some(vec![3; 600]));
+        assert_eq!(file.read_backup(chunk_id).unwrap(), Some(vec![2; 10]));
+        assert_eq!(find_free_sectors(&file.entries, 1), TABLE_SECTORS);
+
+        // Files without backups only held the current entries, at the very start
+        let mut legacy = vec![0; 2 * SECTOR_SIZE as usize];
+        let entry = RegionFileEntry { sector: 2, sectors: 1, length: 4 };
+        let index = RegionFile::entry_index(chunk_id);
+        legacy[index * ENTRY_SIZE..(index + 1) * ENTRY_SIZE]
+            .copy_from_slice(&entry.to_bytes());
+        legacy.extend([4; SECTOR_SIZE as usize]);
+
+        let file = RegionFile::from_bytes(legacy);
+        assert!(file.bytes.starts_with(REGION_FILE_MAGIC));
+        assert_eq!(file.read(chunk_id).unwrap(), Some(vec![4; 4]));
+        assert_eq!(file.read_backup(chunk_id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_migrate_chunk_files() {
+        let slot = SaveSlot::new("region file migration");
+        let region = PlanetLocation::new(IVec2::new(0, 1));
+        let mut tiles = vec![TileType::Soil; TILES_PER_CHUNK];
+        tiles[5] = TileType::Wall;
+
+        // Chunk files had no header and unsigned coordinates
+        let legacy = bincode::serialize(&(&tiles, region, (32u64, 288u64))).unwrap();
+        let storage = save_storage();
+        let key = slot.chunk_path("32_288.chunk");
+        storage.write(&key, &miniz_oxide::deflate::compress_to_vec(&legacy, 6)).unwrap();
+
+        assert_eq!(migrate_chunk_files(&slot).unwrap(), 1);
+        assert!(!storage.exists(&key));
+
+        let chunk_id = ChunkLocation::new(32, 288);
+        let data = read_chunk_data(&slot, chunk_id).unwrap().unwrap();
+        let chunk = decode_data::<Chunk>(&data).unwrap();
+        assert_eq!((chunk.location, chunk.tiles), (chunk_id, tiles));
+
+        // Unreadable files are reported and kept
+        let broken = slot.chunk_path("0_32.chunk");
+        storage.write(&broken, &[1, 2, 3]).unwrap();
+        assert!(migrate_chunk_files(&slot).is_err());
+        assert!(storage.exists(&broken));
+    }
+}
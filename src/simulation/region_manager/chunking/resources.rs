@@ -1,5 +1,9 @@
 use crate::prelude::*;
-use bevy::utils::{HashMap, HashSet};
+use bevy::{
+    tasks::Task,
+    utils::{HashMap, HashSet},
+};
+use std::time::Duration;
 
 //////////////////////////////////////////////////////////////////////////////////////////
 // Chunk System Labels
@@ -12,14 +16,29 @@ pub struct ChunkLoadingStage;
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash, SystemLabel)]
 /// Labels for the systems added by [`VoxelWorldChunkingPlugin`]
 pub enum ChunkLoadingSystem {
-    /// Runs chunk view distance calculations and queue events for chunk creations and
-    /// deletions.
+    /// Runs chunk view distance calculations for every [`ChunkViewer`] and queue events for
+    /// chunk creations and deletions.
     UpdateViewChunks,
     /// Creates the voxel buffers to hold chunk data and attach them a chunk entity in the ECS
     /// world.
     CreateChunks,
+    /// Attaches the chunk data of completed load tasks to their chunk entities and sends
+    /// [`ChunkLoaded`].
+    ProcessChunkLoads,
+    /// Applies the queued tile edits and marks the edited chunks dirty.
+    ApplyTileEdits,
+    /// Starts a background save of the loaded chunks and the game once the [`Autosave`]
+    /// interval elapsed.
+    Autosave,
+    /// Saves and despawns the chunks queued for destruction and sends [`ChunkUnloaded`].
+    DestroyChunks,
+    /// Despawns chunk entities whose save task completed, or sends [`ChunkLoaded`] for
+    /// chunks re-attached while saving.
+    ProcessChunkSaves,
     /// Clears the dirty chunks list.
     ClearDirtyChunks,
+    /// Saves everything before the app exits.
+    FlushSaves,
 }
 
 //////////////////////////////////////////////////////////////////////////////////////////
@@ -49,17 +68,27 @@ impl CurrentLocalPlayerChunk {
     }
 }
 
-// Resource holding the view distance.
-pub struct ChunkLoadRadius {
-    pub horizontal: i32,
-    pub vertical: i32,
+/// Per-frame limits on the chunk creations started by [`create_chunks`]. A `None` limit is
+/// not enforced.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkLoadBudget {
+    /// Maximum number of chunks created per frame.
+    pub max_chunks: Option<usize>,
+    /// Maximum time spent creating chunks per frame, in milliseconds.
+    pub max_millis: Option<f32>,
+}
+
+impl Default for ChunkLoadBudget {
+    fn default() -> Self { Self { max_chunks: Some(8), max_millis: Some(2.0) } }
 }
 
 /// A queue tracking the creation / destroy commands for chunks.
+/// Creations are ordered by distance to the player, closest first.
 #[derive(Default)]
 pub struct ChunkCommandQueue {
-    pub create: Vec<ChunkLocation>,
+    pub create: ChunkPriorityQueue,
     pub destroy: Vec<ChunkLocation>,
+    pub budget: ChunkLoadBudget,
 }
 
 impl ChunkCommandQueue {
@@ -72,7 +101,8 @@ impl ChunkCommandQueue {
 // Chunk Entities
 //////////////////////////////////////////////////////////////////////////////////////////
 
-/// Stores the Entity <-> Chunk voxel data buffer mapping
+/// Stores the Entity <-> Chunk location mapping. The tile data itself lives in the
+/// [`ChunkMap`].
 #[derive(Debug, Default)]
 pub struct ChunkEntities(pub HashMap<ChunkLocation, Entity>);
 
@@ -117,3 +147,44 @@ impl DirtyChunks {
 
     pub fn num_dirty(&self) -> usize { self.0.len() }
 }
+
+/// Holds the chunks modified since they were loaded, which need to be written back to disk
+/// when they unload.
+#[derive(Debug, Default)]
+pub struct ModifiedChunks(pub HashSet<ChunkLocation>);
+
+impl ModifiedChunks {
+    pub fn mark_modified(&mut self, chunk: ChunkLocation) { self.0.insert(chunk); }
+
+    pub fn is_modified(&self, chunk: ChunkLocation) -> bool { self.0.contains(&chunk) }
+
+    /// Clears the modified flag, returning whether it was set.
+    pub fn take(&mut self, chunk: ChunkLocation) -> bool { self.0.remove(&chunk) }
+
+    pub fn num_modified(&self) -> usize { self.0.len() }
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Autosave
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Saves the loaded chunks and the game in the background every `interval`. A `None`
+/// interval turns autosave off.
+pub struct Autosave {
+    pub interval: Option<Duration>,
+    /// Time since the last autosave started.
+    pub elapsed: Duration,
+    /// The autosave being written. Chunks wait for it to complete before they unload, so
+    /// their save can't be overwritten by an older one.
+    pub task: Option<Task<()>>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self { interval: Some(Duration::from_secs(120)), elapsed: Duration::ZERO, task: None }
+    }
+}
+
+impl Autosave {
+    pub fn is_saving(&self) -> bool { self.task.is_some() }
+}
@@ -2,33 +2,40 @@ use ilattice::extent::Extent;
 use ilattice::glam::UVec2;
 use ndshape::Shape;
 
-/// A buffer of typed voxel data stored as a contiguous array in memory.
+use crate::PalettedBuffer;
+
+/// A buffer of typed voxel data, stored as a [`PalettedBuffer`] so chunks made of a
+/// handful of tiles only take a few bits per tile.
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct ChunkBuffer<V, S: Shape<2, Coord = u32>>
 where
-    V: Copy + Clone + Default,
+    V: Copy + Clone + Default + PartialEq,
 {
-    data: Box<[V]>,
+    data: PalettedBuffer<V>,
     shape: S,
 }
 
 #[allow(dead_code)]
 impl<V, S: Shape<2, Coord = u32>> ChunkBuffer<V, S>
 where
-    V: Copy + Clone + Default,
+    V: Copy + Clone + Default + PartialEq,
 {
     #[inline]
     pub fn new(shape: S, initial_val: V) -> Self {
-        Self { data: vec![initial_val; shape.size() as usize].into_boxed_slice(), shape }
+        Self { data: PalettedBuffer::new(shape.size() as usize, initial_val), shape }
     }
 
     #[inline]
     pub fn new_empty(shape: S) -> Self {
-        Self {
-            data: vec![Default::default(); shape.size() as usize].into_boxed_slice(),
-            shape,
-        }
+        Self::new(shape, Default::default())
+    }
+
+    /// Packs existing data, which must be laid out according to `shape`.
+    #[inline]
+    pub fn from_vec(shape: S, data: Vec<V>) -> Self {
+        assert_eq!(data.len(), shape.size() as usize);
+        Self { data: PalettedBuffer::from_slice(&data), shape }
     }
 
     #[inline]
@@ -39,34 +46,41 @@ where
     /// Fills an extent of this buffer with the specified value.
     #[inline]
     pub fn fill_extent(&mut self, extent: Extent<UVec2>, val: V) {
-        ndcopy::fill2(
-            extent.shape.to_array(),
-            val,
-            &mut self.data,
-            &self.shape,
-            extent.minimum.to_array(),
-        );
+        let (min, max) = (extent.minimum, extent.minimum + extent.shape);
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                self.data.set(self.shape.linearize([x, y]) as usize, val);
+            }
+        }
     }
 
     // Returns the voxel at the querried position in local space.
     #[inline]
     pub fn tile_at(&self, pos: UVec2) -> V {
-        self.data[self.shape.linearize(pos.to_array()) as usize]
+        self.data.get(self.shape.linearize(pos.to_array()) as usize)
+    }
+
+    // Sets the voxel at the querried position in local space.
+    #[inline]
+    pub fn set_tile_at(&mut self, pos: UVec2, val: V) {
+        self.data.set(self.shape.linearize(pos.to_array()) as usize, val);
     }
 
-    // Returns a mutable reference to the the voxel at the querried position in local space.
+    /// Iterates over the voxels in the order of the shape.
     #[inline]
-    pub fn tile_at_mut(&mut self, pos: UVec2) -> &mut V {
-        &mut self.data[self.shape.linearize(pos.to_array()) as usize]
+    pub fn iter(&self) -> impl Iterator<Item = V> + '_ {
+        self.data.iter()
     }
 
+    /// Unpacks the voxels into a plain list, in the order of the shape.
     #[inline]
-    pub fn slice(&self) -> &[V] {
-        &self.data
+    pub fn to_vec(&self) -> Vec<V> {
+        self.data.to_vec()
     }
 
+    /// Bytes used by the palette and the packed indices.
     #[inline]
-    pub fn slice_mut(&mut self) -> &mut [V] {
-        &mut self.data
+    pub fn heap_size(&self) -> usize {
+        self.data.heap_size()
     }
 }
@@ -1,10 +1,10 @@
-use ilattice::morton::Morton2i32;
+use ilattice::{extent::Extent, morton::Morton2i32};
 use std::{collections::BTreeMap, hash::Hash};
 
-use bevy::math::IVec2;
+use bevy::math::{IVec2, UVec2};
 use ndshape::Shape;
 
-use crate::ChunkBuffer;
+use crate::{ChunkBuffer, PLANET_TILE_WIDTH};
 
 /// Provides an interface to query or modify voxel data for worlds or scenes split into multiple voxel data buffers of a same shape with no level of detail.
 #[derive(Default)]
@@ -72,4 +72,79 @@ where
     pub fn shape_mask(&self) -> IVec2 {
         self.shape_mask
     }
+
+    /// Returns the minimum of the chunk owning the world-space position. The position is
+    /// wrapped around the east/west edge of the planet first.
+    #[inline]
+    pub fn chunk_min(&self, pos: IVec2) -> IVec2 {
+        IVec2::new(pos.x.rem_euclid(PLANET_TILE_WIDTH), pos.y) & self.shape_mask
+    }
+
+    /// Returns the value at the world-space position, if its chunk is loaded.
+    pub fn get_tile(&self, pos: IVec2) -> Option<V> {
+        let minimum = self.chunk_min(pos);
+        let local = IVec2::new(pos.x.rem_euclid(PLANET_TILE_WIDTH), pos.y) - minimum;
+        self.buffer_at(minimum).map(|buffer| buffer.tile_at(local.as_uvec2()))
+    }
+
+    /// Sets the value at the world-space position. Returns false if its chunk is not loaded.
+    pub fn set_tile(&mut self, pos: IVec2, val: V) -> bool {
+        let minimum = self.chunk_min(pos);
+        let local = IVec2::new(pos.x.rem_euclid(PLANET_TILE_WIDTH), pos.y) - minimum;
+        match self.buffer_at_mut(minimum) {
+            Some(buffer) => {
+                buffer.set_tile_at(local.as_uvec2(), val);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fills the world-space rectangle starting at `min` with `val`, filling the extent of
+    /// each overlapped chunk at once. Returns the minimums of the loaded chunks that were
+    /// filled.
+    pub fn fill_rect(&mut self, min: IVec2, size: UVec2, val: V) -> Vec<IVec2> {
+        let chunk_size = !self.shape_mask + IVec2::ONE;
+        let max = min + size.as_ivec2();
+        let mut filled = Vec::new();
+
+        let mut chunk_y = min.y & self.shape_mask.y;
+        while chunk_y < max.y {
+            let mut chunk_x = min.x & self.shape_mask.x;
+            while chunk_x < max.x {
+                let chunk_origin = IVec2::new(chunk_x, chunk_y);
+                let lo = min.max(chunk_origin);
+                let hi = max.min(chunk_origin + chunk_size);
+
+                let minimum = self.chunk_min(chunk_origin);
+                if let Some(buffer) = self.buffer_at_mut(minimum) {
+                    let extent = Extent::from_min_and_shape(
+                        (lo - chunk_origin).as_uvec2(),
+                        (hi - lo).as_uvec2(),
+                    );
+                    buffer.fill_extent(extent, val);
+                    filled.push(minimum);
+                }
+
+                chunk_x += chunk_size.x;
+            }
+            chunk_y += chunk_size.y;
+        }
+
+        filled
+    }
+
+    /// Returns an iterator over the loaded buffers and their minimums.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &ChunkBuffer<V, S>)> {
+        self.chunks.iter().map(|(minimum, buffer)| (IVec2::from(*minimum), buffer))
+    }
+
+    /// Return the number of loaded buffers.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
 }
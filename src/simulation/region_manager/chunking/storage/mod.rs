@@ -3,8 +3,10 @@ use ndshape::ConstShape2u32;
 
 mod buffer;
 mod chunk_map;
+mod palette;
 
 pub use buffer::*;
 pub use chunk_map::*;
+pub use palette::*;
 
 pub type ChunkShape = ConstShape2u32<CHUNK_SIZE_U32, CHUNK_SIZE_U32>;
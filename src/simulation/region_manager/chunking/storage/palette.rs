@@ -0,0 +1,354 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Values stored as indices into a palette of the distinct values, bit-packed in as few bits
+/// as the palette needs. Chunks are mostly runs of a handful of tiles, so this takes a
+/// fraction of the memory of a plain buffer, and a chunk of a single tile takes no index
+/// bits at all. Backs the [`ChunkBuffer`]s of loaded chunks, and saved chunks through
+/// [`paletted_tiles`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PalettedBuffer<V> {
+    palette: Vec<V>,
+    bits: u32,
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl<V: Copy + PartialEq> PalettedBuffer<V> {
+    /// A buffer of `len` copies of `value`.
+    pub fn new(len: usize, value: V) -> Self {
+        Self { palette: vec![value], bits: 0, len, words: Vec::new() }
+    }
+
+    pub fn from_slice(values: &[V]) -> Self {
+        let mut palette = Vec::new();
+        let indices =
+            values.iter().map(|value| palette_index(&mut palette, *value)).collect::<Vec<_>>();
+
+        let bits = bits_for(palette.len());
+        Self { palette, bits, len: values.len(), words: pack(&indices, bits) }
+    }
+
+    /// Rebuilds a buffer from the output of [`Self::runs`]. Returns `None` if a run points
+    /// outside of the palette.
+    pub fn from_runs(palette: Vec<V>, runs: &[(u16, u16)]) -> Option<Self> {
+        if runs.iter().any(|(index, _)| *index as usize >= palette.len()) {
+            return None;
+        }
+
+        let indices = runs
+            .iter()
+            .flat_map(|(index, length)| {
+                std::iter::repeat(*index as usize).take(*length as usize)
+            })
+            .collect::<Vec<_>>();
+
+        let bits = bits_for(palette.len());
+        Some(Self { palette, bits, len: indices.len(), words: pack(&indices, bits) })
+    }
+
+    pub fn get(&self, i: usize) -> V { self.palette[self.index(i)] }
+
+    /// Sets the value at `i`, growing the palette and the index size as needed. Values that
+    /// are no longer used stay in the palette.
+    pub fn set(&mut self, i: usize, value: V) {
+        let index = palette_index(&mut self.palette, value);
+
+        let bits = bits_for(self.palette.len());
+        if bits > self.bits {
+            let indices = (0..self.len).map(|i| self.index(i)).collect::<Vec<_>>();
+            self.words = pack(&indices, bits);
+            self.bits = bits;
+        }
+
+        if self.bits > 0 {
+            let (word, shift) = self.position(i);
+            let mask = (1 << self.bits) - 1;
+            self.words[word] =
+                (self.words[word] & !(mask << shift)) | ((index as u64) << shift);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = V> + '_ { (0..self.len).map(|i| self.get(i)) }
+
+    pub fn to_vec(&self) -> Vec<V> { self.iter().collect() }
+
+    /// Bytes used by the palette and the indices.
+    pub fn heap_size(&self) -> usize {
+        self.palette.len() * std::mem::size_of::<V>() + self.words.len() * 8
+    }
+
+    /// The indices as runs of (palette index, length).
+    pub fn runs(&self) -> Vec<(u16, u16)> {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for i in 0..self.len {
+            let index = self.index(i) as u16;
+            match runs.last_mut() {
+                Some((last, length)) if *last == index && *length < u16::MAX => *length += 1,
+                _ => runs.push((index, 1)),
+            }
+        }
+
+        runs
+    }
+
+    fn index(&self, i: usize) -> usize {
+        assert!(i < self.len, "index {i} out of bounds for a buffer of {}", self.len);
+        if self.bits == 0 {
+            return 0;
+        }
+
+        let (word, shift) = self.position(i);
+        ((self.words[word] >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    /// The word holding index `i`, and its offset in the word. Indices never straddle two
+    /// words.
+    fn position(&self, i: usize) -> (usize, u32) {
+        let per_word = (u64::BITS / self.bits) as usize;
+        (i / per_word, (i % per_word) as u32 * self.bits)
+    }
+}
+
+fn palette_index<V: PartialEq>(palette: &mut Vec<V>, value: V) -> usize {
+    match palette.iter().position(|v| *v == value) {
+        Some(index) => index,
+        None => {
+            palette.push(value);
+            palette.len() - 1
+        }
+    }
+}
+
+/// Bits needed to index a palette of `len` values.
+fn bits_for(len: usize) -> u32 {
+    if len <= 1 {
+        0
+    } else {
+        usize::BITS - (len - 1).leading_zeros()
+    }
+}
+
+fn pack(indices: &[usize], bits: u32) -> Vec<u64> {
+    if bits == 0 {
+        return Vec::new();
+    }
+
+    let per_word = (u64::BITS / bits) as usize;
+    indices
+        .chunks(per_word)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |word, (i, index)| word | ((*index as u64) << (i as u32 * bits)))
+        })
+        .collect()
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Tile Encoding
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// How tiles are written to disk, whichever of the two is smaller.
+#[derive(Serialize, Deserialize)]
+enum TileEncoding<V> {
+    Runs { palette: Vec<V>, runs: Vec<(u16, u16)> },
+    Packed(PalettedBuffer<V>),
+}
+
+impl<V: Copy + PartialEq> TileEncoding<V> {
+    fn encode(tiles: &[V]) -> Self {
+        let packed = PalettedBuffer::from_slice(tiles);
+        let runs = packed.runs();
+
+        // Both share the palette, compare the size of the indices
+        if runs.len() * 4 < packed.words.len() * 8 {
+            Self::Runs { palette: packed.palette, runs }
+        } else {
+            Self::Packed(packed)
+        }
+    }
+
+    fn decode(self) -> Option<Vec<V>> {
+        let packed = match self {
+            Self::Runs { palette, runs } => PalettedBuffer::from_runs(palette, &runs)?,
+            Self::Packed(packed) => packed,
+        };
+
+        // Reject data that would index out of the words or the palette
+        let valid = packed.bits == bits_for(packed.palette.len())
+            && (packed.bits == 0
+                || packed.words.len() * (u64::BITS / packed.bits) as usize >= packed.len)
+            && (0..packed.len).all(|i| packed.index(i) < packed.palette.len());
+        valid.then(|| packed.to_vec())
+    }
+}
+
+/// Serializes tiles with a palette and run-length or bit-packed indices, for use with
+/// `#[serde(with = "paletted_tiles")]`. Human readable formats get the plain tiles, so
+/// exported saves stay editable.
+pub mod paletted_tiles {
+    use super::*;
+
+    pub fn serialize<S, V>(tiles: &[V], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        V: Serialize + Copy + PartialEq,
+    {
+        if serializer.is_human_readable() {
+            tiles.serialize(serializer)
+        } else {
+            TileEncoding::encode(tiles).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<Vec<V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de> + Copy + PartialEq,
+    {
+        if deserializer.is_human_readable() {
+            Vec::deserialize(deserializer)
+        } else {
+            TileEncoding::deserialize(deserializer)?
+                .decode()
+                .ok_or_else(|| D::Error::custom("tile indices point outside of the palette"))
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Compression Report
+//////////////////////////////////////////////////////////////////////////////////////////
+
+/// Compares the size of chunk tiles saved as a plain list and with [`paletted_tiles`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionReport {
+    pub chunks: usize,
+    pub raw_bytes: u64,
+    pub encoded_bytes: u64,
+}
+
+impl CompressionReport {
+    pub fn measure<'a, V>(chunks: impl IntoIterator<Item = &'a [V]>) -> Self
+    where
+        V: Serialize + Copy + PartialEq + 'a,
+    {
+        let mut report = Self::default();
+        for tiles in chunks {
+            report.chunks += 1;
+            report.raw_bytes += bincode::serialized_size(tiles).unwrap_or(0);
+            report.encoded_bytes +=
+                bincode::serialized_size(&TileEncoding::encode(tiles)).unwrap_or(0);
+        }
+
+        report
+    }
+
+    /// Percentage of the raw size saved by the encoding.
+    pub fn reduction(&self) -> f32 {
+        if self.raw_bytes == 0 {
+            return 0.0;
+        }
+        100.0 * (1.0 - self.encoded_bytes as f32 / self.raw_bytes as f32)
+    }
+}
+
+impl std::fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} chunks: {} bytes of tiles, {} bytes paletted ({:.1}% smaller)",
+            self.chunks,
+            self.raw_bytes,
+            self.encoded_bytes,
+            self.reduction()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use bracket_random::prelude::RandomNumberGenerator;
+    use ilattice::extent::Extent;
+
+    /// Tiles laid out like a generated region: bands of soil and sand, dotted with plants.
+    fn region_chunk(seed: u64) -> Vec<TileType> {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        (0..TILES_PER_CHUNK)
+            .map(|i| match (i / CHUNK_SIZE + seed as usize) % 12 {
+                0..=6 if rng.range(0, 10) == 0 => TileType::Plant(PlantType::Grass),
+                0..=6 => TileType::Soil,
+                7..=10 => TileType::Sand,
+                _ => TileType::Water,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_paletted_buffer() {
+        let tiles = region_chunk(1);
+        let mut buffer = PalettedBuffer::from_slice(&tiles);
+        assert_eq!(buffer.to_vec(), tiles);
+        assert_eq!(buffer.bits, 2);
+
+        // A fifth tile type needs a wider index
+        buffer.set(10, TileType::Wall);
+        buffer.set(11, TileType::Tree(TreeType::Evergreen));
+        assert_eq!(buffer.bits, 3);
+        assert_eq!(buffer.get(10), TileType::Wall);
+        assert_eq!(buffer.get(12), tiles[12]);
+
+        let rebuilt = PalettedBuffer::from_runs(buffer.palette.clone(), &buffer.runs());
+        assert_eq!(rebuilt.unwrap().to_vec(), buffer.to_vec());
+
+        let uniform = PalettedBuffer::new(TILES_PER_CHUNK, TileType::Soil);
+        assert_eq!((uniform.bits, uniform.get(5)), (0, TileType::Soil));
+    }
+
+    #[test]
+    fn test_chunk_buffer() {
+        let mut buffer = ChunkBuffer::new(ChunkShape {}, TileType::Soil);
+        assert_eq!(buffer.heap_size(), std::mem::size_of::<TileType>());
+
+        let extent = Extent::from_min_and_shape(UVec2::new(2, 3), UVec2::new(4, 2));
+        buffer.fill_extent(extent, TileType::Sand);
+        buffer.set_tile_at(UVec2::ZERO, TileType::Wall);
+        assert_eq!(buffer.tile_at(UVec2::ZERO), TileType::Wall);
+        assert_eq!(buffer.tile_at(UVec2::new(5, 4)), TileType::Sand);
+        assert_eq!(buffer.tile_at(UVec2::new(6, 4)), TileType::Soil);
+        assert_eq!(buffer.iter().filter(|tile| *tile == TileType::Sand).count(), 8);
+
+        // Three tile types take two bits each
+        let words = (TILES_PER_CHUNK * 2 + 63) / 64;
+        assert_eq!(buffer.heap_size(), 3 * std::mem::size_of::<TileType>() + words * 8);
+    }
+
+    #[test]
+    fn test_chunk_round_trip() {
+        let region = PlanetLocation::new(IVec2::ZERO);
+        for tiles in [region_chunk(3), vec![TileType::Sand; TILES_PER_CHUNK]] {
+            let mut chunk = Chunk::new(region, ChunkLocation::new(0, 0));
+            chunk.tiles = tiles.clone();
+
+            let bytes = encode_data(&chunk).unwrap();
+            assert_eq!(decode_data::<Chunk>(&bytes).unwrap().tiles, tiles);
+        }
+    }
+
+    #[test]
+    fn test_compression_report() {
+        setup_test_planet();
+        let region_idx = PlanetLocation::new(IVec2::new(2, 2)).to_region_index();
+        let chunks = AllChunksIterator::new()
+            .map(|chunk| populate_region_chunk(region_idx, chunk).tiles)
+            .collect::<Vec<_>>();
+        let report = CompressionReport::measure(chunks.iter().map(Vec::as_slice));
+        println!("Generated region: {report}");
+
+        assert_eq!(report.chunks, CHUNKS_PER_REGION);
+        assert!(report.reduction() > 50.0, "{report}");
+    }
+}
@@ -1,32 +1,168 @@
 use crate::prelude::*;
+use bevy::{
+    tasks::{AsyncComputeTaskPool, IoTaskPool},
+    utils::{HashSet, Instant},
+};
 
-/// Creates the requested chunks and attach them an ECS entity.
+/// Creates the requested chunks and attach them an ECS entity, within the per-frame
+/// [`ChunkLoadBudget`].
+/// Chunk data is loaded in the background and attached by [`process_chunk_load`].
 pub fn create_chunks(
     mut cmds: Commands,
+    slot: Res<SaveSlot>,
+    saving_q: Query<(Entity, &ChunkSaveTask)>,
     mut chunk_entities: ResMut<ChunkEntities>,
     mut chunks_command_queue: ResMut<ChunkCommandQueue>,
 ) {
-    chunks_command_queue.create.drain(..).for_each(|chunk_create_location| {
-        let chunk = load_chunk(chunk_create_location);
-        chunk_entities.attach_entity(chunk_create_location, cmds.spawn().insert(chunk).id())
-    });
+    let task_pool = AsyncComputeTaskPool::get();
+    let budget = chunks_command_queue.budget;
+    let started = Instant::now();
+    let mut created = 0;
+
+    while !chunks_command_queue.create.is_empty() {
+        let over_count = budget.max_chunks.map_or(false, |max| created >= max);
+        let over_time = budget
+            .max_millis
+            .map_or(false, |max| started.elapsed().as_secs_f32() * 1000.0 >= max);
+        if over_count || over_time {
+            break;
+        }
+
+        let chunk_create_location = chunks_command_queue.create.pop().unwrap();
+        if chunk_entities.entity(chunk_create_location).is_some() {
+            continue;
+        }
+
+        // A save still in flight holds the latest data for this chunk, so re-attach its
+        // entity instead of reading a file that is being written.
+        let saving_entity = saving_q
+            .iter()
+            .find(|(_, task)| task.0 == chunk_create_location && task.1.is_some())
+            .map(|(entity, _)| entity);
+
+        let chunk_entity = match saving_entity {
+            Some(entity) => entity,
+            None => {
+                let slot = slot.clone();
+                let task =
+                    task_pool.spawn(async move { load_chunk(&slot, chunk_create_location) });
+                cmds.spawn().insert(ChunkLoadTask(chunk_create_location, task)).id()
+            }
+        };
+
+        chunk_entities.attach_entity(chunk_create_location, chunk_entity);
+        created += 1;
+    }
 }
 
+/// Saves and despawns the chunks queued for destruction, along with the [`Persistent`]
+/// entities standing in them. Only chunks modified since they were loaded, or holding
+/// entities, are written back. Chunks that are still loading or saving stay queued until
+/// their task completes, and so does every chunk while an [`Autosave`] is being written.
 pub fn destroy_chunks(
     mut commands: Commands,
-    chunks_q: Query<&Chunk>,
+    slot: Res<SaveSlot>,
+    autosave: Res<Autosave>,
+    chunks_q: Query<&ChunkHandle, Without<ChunkSaveTask>>,
+    persistent_q: ChunkPersistentQuery,
     mut chunk_entities: ResMut<ChunkEntities>,
+    mut modified_chunks: ResMut<ModifiedChunks>,
     mut chunks: ResMut<ChunkMap<TileType, ChunkShape>>,
+    mut elevations: ResMut<ChunkMap<u32, ChunkShape>>,
     mut chunks_command_queue: ResMut<ChunkCommandQueue>,
+    mut chunk_unloaded: EventWriter<ChunkUnloaded>,
 ) {
-    for chunk_destroy_location in chunks_command_queue.destroy.drain(..) {
-        let chunk_entity = chunk_entities.detach_entity(chunk_destroy_location).unwrap();
-        let chunk = chunks_q.get(chunk_entity).unwrap();
+    let task_pool = IoTaskPool::get();
+    if chunks_command_queue.destroy.is_empty() || autosave.is_saving() {
+        return;
+    }
+
+    let mut chunk_persistents = chunk_persistents(&persistent_q);
+
+    chunks_command_queue.destroy.retain(|chunk_destroy_location| {
+        let chunk_entity = match chunk_entities.entity(*chunk_destroy_location) {
+            Some(entity) => entity,
+            None => return false,
+        };
+
+        let handle = match chunks_q.get(chunk_entity) {
+            Ok(handle) => *handle,
+            Err(_) => return true,
+        };
+
+        chunk_entities.detach_entity(*chunk_destroy_location);
+        chunk_unloaded.send(ChunkUnloaded(*chunk_destroy_location));
+        let entities = chunk_persistents.remove(chunk_destroy_location).unwrap_or_default();
+        for (entity, _) in entities.iter() {
+            commands.entity(*entity).despawn_recursive();
+        }
 
-        save_chunk(chunk);
+        let modified = modified_chunks.take(*chunk_destroy_location);
+        let elevation = elevations.remove(chunk_destroy_location.as_ivec2());
+        let buffer = match chunks.remove(chunk_destroy_location.as_ivec2()) {
+            Some(buffer) if modified || !entities.is_empty() => buffer,
+            _ => {
+                commands.entity(chunk_entity).despawn_recursive();
+                return false;
+            }
+        };
+
+        let entities = entities.into_iter().map(|(_, saved)| saved).collect();
+        let elevation = elevation.unwrap_or_else(|| ChunkBuffer::new_empty(ChunkShape {}));
+        let chunk =
+            Chunk::from_buffer(handle.region, handle.location, &buffer, &elevation, entities);
+
+        let slot = slot.clone();
+        let task = task_pool.spawn(async move {
+            save_chunk(&slot, &chunk);
+            chunk
+        });
+
+        commands
+            .entity(chunk_entity)
+            .remove::<ChunkHandle>()
+            .insert(ChunkSaveTask(*chunk_destroy_location, Some(task)));
+
+        false
+    });
+}
+
+/// Applies the queued [`TileEdit`]s to the loaded chunks, marking every edited chunk dirty and
+/// modified since load.
+pub fn apply_tile_edits(
+    mut tile_edits: ResMut<Events<TileEdit>>,
+    mut dirty_chunks: ResMut<DirtyChunks>,
+    mut modified_chunks: ResMut<ModifiedChunks>,
+    mut chunks: ResMut<ChunkMap<TileType, ChunkShape>>,
+    mut chunk_modified: EventWriter<ChunkModified>,
+) {
+    let mut edited_chunks = HashSet::default();
+
+    for edit in tile_edits.drain() {
+        match edit {
+            TileEdit::Set(pos, tile) => {
+                if chunks.set_tile(pos, tile) {
+                    edited_chunks.insert(chunks.chunk_min(pos));
+                }
+            }
+            TileEdit::Fill { min, size, tile } => {
+                edited_chunks.extend(chunks.fill_rect(min, size, tile));
+            }
+            TileEdit::Batch(tiles) => {
+                for (pos, tile) in tiles {
+                    if chunks.set_tile(pos, tile) {
+                        edited_chunks.insert(chunks.chunk_min(pos));
+                    }
+                }
+            }
+        }
+    }
 
-        chunks.remove(chunk_destroy_location.as_ivec2());
-        commands.entity(chunk_entity).despawn_recursive();
+    for chunk_min in edited_chunks {
+        let chunk_location = ChunkLocation::from(chunk_min);
+        dirty_chunks.mark_dirty(chunk_location);
+        modified_chunks.mark_modified(chunk_location);
+        chunk_modified.send(ChunkModified(chunk_location));
     }
 }
 
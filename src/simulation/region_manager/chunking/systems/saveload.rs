@@ -1,71 +1,339 @@
 use crate::prelude::*;
-use bevy::tasks::Task;
+use bevy::{
+    app::AppExit,
+    tasks::{IoTaskPool, Task},
+    utils::HashMap,
+};
+use futures_lite::future;
+use std::time::Duration;
+
+/// The [`Persistent`] entities saved with the chunk they stand in. Viewers keep their own
+/// chunks loaded, so they never unload with one, and the player is saved with the game.
+pub type ChunkPersistentQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, PersistentComponents<'static>),
+    (With<Persistent>, Without<Player>, Without<ChunkViewer>),
+>;
+
+/// The player, saved in the [`GameSave`].
+pub type PlayerPersistentQuery<'w, 's> =
+    Query<'w, 's, PersistentComponents<'static>, (With<Player>, With<Persistent>)>;
+
+/// Groups the entities of a [`ChunkPersistentQuery`] by the chunk they stand in.
+pub fn chunk_persistents(
+    persistent_q: &ChunkPersistentQuery,
+) -> HashMap<ChunkLocation, Vec<(Entity, SavedEntity)>> {
+    let mut persistents: HashMap<ChunkLocation, Vec<(Entity, SavedEntity)>> =
+        HashMap::default();
+    for (entity, components) in persistent_q.iter() {
+        let chunk_location = components.0.chunk_location().wrapped();
+        let saved = SavedEntity::capture(components);
+        persistents.entry(chunk_location).or_default().push((entity, saved));
+    }
+
+    persistents
+}
 
 //////////////////////////////////////////////////////////////////////////////////////////
 // Chunk Save
 //////////////////////////////////////////////////////////////////////////////////////////
 
+/// A background save of a chunk's data, returning the data once it is on disk. The task is
+/// taken once it completes, a completed task must not be polled again.
 #[derive(Debug, Component)]
-pub struct ChunkSaveTask(pub Task<()>);
+pub struct ChunkSaveTask(pub ChunkLocation, pub Option<Task<Chunk>>);
 
-pub fn chunk_filename(chunk_id: ChunkLocation) -> String {
-    chunk_save_location(&format!("{}_{}.chunk", chunk_id.x, chunk_id.y))
+/// Returns the name of the file the chunk is saved in, see [`RegionFile`].
+pub fn chunk_filename(slot: &SaveSlot, chunk_id: ChunkLocation) -> String {
+    region_filename(slot, chunk_id)
 }
 
-pub fn save_chunk(chunk: &Chunk) {
+pub fn save_chunk(slot: &SaveSlot, chunk: &Chunk) {
     let chunk_id = chunk.location;
-    if let Err(err) = save_data(chunk_filename(chunk_id), chunk) {
-        println!("Failed to save chunk: {err:?}");
+    if let Err(err) =
+        encode_data(chunk).and_then(|data| write_chunk_data(slot, chunk_id, &data))
+    {
+        println!("Failed to save chunk: {}", err.in_chunk(chunk_id).report());
+    }
+}
+
+/// Despawns chunk entities once their save task completes. Chunks that were re-attached
+/// while saving get their data back instead.
+pub fn process_chunk_save(
+    mut commands: Commands,
+    chunk_entities: Res<ChunkEntities>,
+    mut modified_chunks: ResMut<ModifiedChunks>,
+    mut chunks: ResMut<ChunkMap<TileType, ChunkShape>>,
+    mut elevations: ResMut<ChunkMap<u32, ChunkShape>>,
+    mut chunk_loaded: EventWriter<ChunkLoaded>,
+    mut saved_chunks: Query<(Entity, &mut ChunkSaveTask)>,
+) {
+    for (chunk_entity, mut task) in saved_chunks.iter_mut() {
+        let saved = task.1.as_mut().and_then(|save| future::block_on(future::poll_once(save)));
+        if let Some(mut chunk) = saved {
+            task.1 = None;
+            if chunk_entities.entity(task.0) == Some(chunk_entity) {
+                restore_chunk_entities(&mut commands, &mut modified_chunks, &mut chunk);
+                let handle = chunk.handle();
+                let location = chunk.location.as_ivec2();
+                let (tiles, elevation) = chunk.into_buffers();
+                chunks.insert(location, tiles);
+                elevations.insert(location, elevation);
+                commands.entity(chunk_entity).remove::<ChunkSaveTask>().insert(handle);
+                chunk_loaded.send(ChunkLoaded(handle.location, chunk_entity));
+            } else {
+                commands.entity(chunk_entity).despawn_recursive();
+            }
+        }
     }
 }
 
-// pub fn process_chunk_save(
-//     mut commands: Commands,
-//     mut chunk_entities: ResMut<ChunkEntities>,
-//     mut chunks: ResMut<ChunkMap<TileType, ChunkShape>>,
-//     mut saved_chunks: Query<(Entity, &Chunk, &mut ChunkSaveTask)>,
-// ) {
-//     for (chunk_entity, chunk, mut task) in saved_chunks.iter_mut() {
-//         if future::block_on(future::poll_once(&mut task.0)).is_some() {
-//             chunks.remove(chunk.location.as_ivec2());
-//             let other_entity = chunk_entities.detach_entity(chunk.location).unwrap();
-
-//             println!("Chunk entity: {chunk_entity:?} / Other: {other_entity:?}");
-
-//             commands.entity(chunk_entity).remove::<ChunkSaveTask>();
-//             commands.entity(chunk_entity).despawn_recursive();
-//             commands.entity(other_entity).despawn_recursive();
-//         }
-//     }
-// }
+/// Copies the loaded chunks that need saving: those modified since they were last saved,
+/// and those holding entities. Chunks that are loading or saving are skipped, their task
+/// holds their data. The copied chunks are no longer modified, except the ones holding
+/// entities, which are saved again when they unload in case the entities moved.
+pub fn snapshot_chunks(
+    chunk_entities: &ChunkEntities,
+    chunks_q: &Query<&ChunkHandle>,
+    persistent_q: &ChunkPersistentQuery,
+    modified_chunks: &mut ModifiedChunks,
+    chunks: &ChunkMap<TileType, ChunkShape>,
+    elevations: &ChunkMap<u32, ChunkShape>,
+) -> Vec<Chunk> {
+    let mut persistents = chunk_persistents(persistent_q);
+
+    chunk_entities
+        .iter()
+        .filter_map(|(location, entity)| {
+            let handle = chunks_q.get(*entity).ok()?;
+            let buffer = chunks.buffer_at(location.as_ivec2())?;
+            let elevation = elevations.buffer_at(location.as_ivec2())?;
+
+            let entities = persistents.remove(location).unwrap_or_default();
+            let modified = modified_chunks.take(*location);
+            if !entities.is_empty() {
+                modified_chunks.mark_modified(*location);
+            } else if !modified {
+                return None;
+            }
+
+            let entities = entities.into_iter().map(|(_, saved)| saved).collect();
+            Some(Chunk::from_buffer(
+                handle.region,
+                handle.location,
+                buffer,
+                elevation,
+                entities,
+            ))
+        })
+        .collect()
+}
+
+fn save_game_state(slot: &SaveSlot, chunks: &[Chunk], game: Option<&GameSave>) {
+    for chunk in chunks {
+        save_chunk(slot, chunk);
+    }
+
+    if let Some(game) = game {
+        if let Err(err) = save_game(slot, game) {
+            println!("Error saving game: {}", err.report());
+        }
+    }
+
+    // Chunk saves in between are batched, the storage writes them out here
+    if let Err(err) = save_storage().flush() {
+        println!("Error flushing saves: {}", err.report());
+    }
+}
+
+/// Saves the loaded chunks and the game in the background, every [`Autosave`] interval.
+pub fn autosave(
+    time: Res<Time>,
+    slot: Res<SaveSlot>,
+    mut autosave: ResMut<Autosave>,
+    chunk_entities: Res<ChunkEntities>,
+    chunks_q: Query<&ChunkHandle>,
+    persistent_q: ChunkPersistentQuery,
+    player_q: PlayerPersistentQuery,
+    mut modified_chunks: ResMut<ModifiedChunks>,
+    chunks: Res<ChunkMap<TileType, ChunkShape>>,
+    elevations: Res<ChunkMap<u32, ChunkShape>>,
+) {
+    if let Some(task) = autosave.task.as_mut() {
+        if future::block_on(future::poll_once(task)).is_none() {
+            return;
+        }
+        autosave.task = None;
+    }
+
+    let interval = match autosave.interval {
+        Some(interval) => interval,
+        None => return,
+    };
+    autosave.elapsed += time.delta();
+    if autosave.elapsed < interval {
+        return;
+    }
+    autosave.elapsed = Duration::ZERO;
+
+    let chunks = snapshot_chunks(
+        &chunk_entities,
+        &chunks_q,
+        &persistent_q,
+        &mut modified_chunks,
+        &chunks,
+        &elevations,
+    );
+    let game = player_q
+        .get_single()
+        .ok()
+        .map(|player| GameSave { player: SavedEntity::capture(player) });
+
+    let slot = slot.clone();
+    autosave.task = Some(IoTaskPool::get().spawn(async move {
+        save_game_state(&slot, &chunks, game.as_ref());
+    }));
+}
+
+/// Saves everything before the app exits. The running autosave and chunk saves are waited
+/// on, then the loaded chunks and the game are saved on this thread.
+pub fn flush_saves_on_exit(
+    slot: Res<SaveSlot>,
+    app_exit_events: EventReader<AppExit>,
+    mut autosave: ResMut<Autosave>,
+    chunk_entities: Res<ChunkEntities>,
+    chunks_q: Query<&ChunkHandle>,
+    mut saving_q: Query<&mut ChunkSaveTask>,
+    persistent_q: ChunkPersistentQuery,
+    player_q: PlayerPersistentQuery,
+    mut modified_chunks: ResMut<ModifiedChunks>,
+    chunks: Res<ChunkMap<TileType, ChunkShape>>,
+    elevations: Res<ChunkMap<u32, ChunkShape>>,
+) {
+    if app_exit_events.is_empty() {
+        return;
+    }
+
+    if let Some(task) = autosave.task.take() {
+        future::block_on(task);
+    }
+    for mut task in saving_q.iter_mut() {
+        if let Some(save) = task.1.take() {
+            future::block_on(save);
+        }
+    }
+
+    let chunks = snapshot_chunks(
+        &chunk_entities,
+        &chunks_q,
+        &persistent_q,
+        &mut modified_chunks,
+        &chunks,
+        &elevations,
+    );
+    let game = player_q
+        .get_single()
+        .ok()
+        .map(|player| GameSave { player: SavedEntity::capture(player) });
+    save_game_state(&slot, &chunks, game.as_ref());
+    println!("Saved {} chunks before exiting", chunks.len());
+}
 
 //////////////////////////////////////////////////////////////////////////////////////////
 // Chunk Load
 //////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Component)]
-pub struct ChunkLoadTask(pub Task<Chunk>);
-
-pub fn load_chunk(chunk_id: ChunkLocation) -> Chunk {
-    match load_data::<Chunk>(chunk_filename(chunk_id)) {
-        Ok(chunk) => chunk,
-        Err(_) => {
-            panic!("Failed to load chunk: {chunk_id:?}");
-            // let planet_loc = chunk_id.to_planet_location();
-            // build_chunk(planet_loc, chunk_id)
+pub struct ChunkLoadTask(pub ChunkLocation, pub Task<Result<Chunk, IOError>>);
+
+/// Loads a chunk from disk. Chunks that were never written are built from the global planet
+/// and persisted, so the world extends past the embark region. Chunks whose data is damaged
+/// load their backup, without one they're left alone and returned as an error, as are chunks
+/// saved by a newer version of the game.
+pub fn load_chunk(slot: &SaveSlot, chunk_id: ChunkLocation) -> Result<Chunk, IOError> {
+    let saved = read_chunk_data(slot, chunk_id)
+        .and_then(|data| data.map(|d| decode_data(&d)).transpose());
+    let err = match saved {
+        Ok(Some(chunk)) => return Ok(fill_chunk_elevation(chunk)),
+        Ok(None) => {
+            let chunk = build_chunk(chunk_id);
+            save_chunk(slot, &chunk);
+            return Ok(chunk);
+        }
+        Err(err) => err.in_chunk(chunk_id),
+    };
+
+    // Fall back on the data the chunk held before its last save. The damaged data is left in
+    // place, it's only replaced once the chunk is saved again.
+    if !matches!(err.root(), IOError::SaveFileTooNew { .. }) {
+        let backup = read_chunk_backup(slot, chunk_id)
+            .and_then(|data| data.map(|d| decode_data::<Chunk>(&d)).transpose());
+        if let Ok(Some(chunk)) = backup {
+            println!("Failed to load chunk {chunk_id:?}, using its backup: {}", err.report());
+            return Ok(fill_chunk_elevation(chunk));
         }
     }
+
+    Err(err.in_world(slot))
 }
 
-// pub fn process_chunk_load(
-//     mut commands: Commands,
-//     mut chunk_entities: ResMut<ChunkEntities>,
-//     mut saved_chunks: Query<(Entity, &mut ChunkLoadTask)>,
-// ) {
-//     for (chunk_entity, mut task) in saved_chunks.iter_mut() {
-//         if let Some(chunk) = future::block_on(future::poll_once(&mut task.0)) {
-//             chunk_entities.attach_entity(chunk.location,
-// commands.spawn().insert(chunk).id());
-// commands.entity(chunk_entity).despawn_recursive();         }
-//     }
-// }
+/// Chunks saved before they stored their elevation get the elevation of the planet's terrain.
+fn fill_chunk_elevation(mut chunk: Chunk) -> Chunk {
+    if chunk.elevation.is_empty() {
+        chunk.elevation = chunk_elevation(chunk.location);
+    }
+    chunk
+}
+
+/// Spawns the entities saved with a chunk. The chunk is marked modified, so its save no
+/// longer lists them once it unloads again.
+fn restore_chunk_entities(
+    commands: &mut Commands,
+    modified_chunks: &mut ModifiedChunks,
+    chunk: &mut Chunk,
+) {
+    let entities = std::mem::take(&mut chunk.entities);
+    for saved in entities.iter() {
+        saved.spawn(commands);
+    }
+
+    if !entities.is_empty() {
+        modified_chunks.mark_modified(chunk.location);
+    }
+}
+
+/// Moves the chunk data into the [`ChunkMap`] once the load task completes, tags its entity
+/// with a [`ChunkHandle`], restores its entities and sends [`ChunkLoaded`]. Chunks that
+/// failed to load are reported and left unloaded.
+pub fn process_chunk_load(
+    mut commands: Commands,
+    mut errors: ResMut<ErrorDialog>,
+    mut chunk_entities: ResMut<ChunkEntities>,
+    mut modified_chunks: ResMut<ModifiedChunks>,
+    mut chunks: ResMut<ChunkMap<TileType, ChunkShape>>,
+    mut elevations: ResMut<ChunkMap<u32, ChunkShape>>,
+    mut chunk_loaded: EventWriter<ChunkLoaded>,
+    mut loading_chunks: Query<(Entity, &mut ChunkLoadTask)>,
+) {
+    for (chunk_entity, mut task) in loading_chunks.iter_mut() {
+        match future::block_on(future::poll_once(&mut task.1)) {
+            Some(Ok(mut chunk)) => {
+                restore_chunk_entities(&mut commands, &mut modified_chunks, &mut chunk);
+                let handle = chunk.handle();
+                let location = chunk.location.as_ivec2();
+                let (tiles, elevation) = chunk.into_buffers();
+                chunks.insert(location, tiles);
+                elevations.insert(location, elevation);
+                commands.entity(chunk_entity).remove::<ChunkLoadTask>().insert(handle);
+                chunk_loaded.send(ChunkLoaded(handle.location, chunk_entity));
+            }
+            Some(Err(err)) => {
+                errors.report("Failed to load chunk", &err);
+                chunk_entities.detach_entity(task.0);
+                commands.entity(chunk_entity).despawn_recursive();
+            }
+            None => {}
+        }
+    }
+}
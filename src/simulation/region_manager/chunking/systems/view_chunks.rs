@@ -1,66 +1,99 @@
 use crate::prelude::*;
-use bevy::utils::FloatOrd;
+use bevy::utils::HashMap;
 
 /// Run criteria for the [`update_view_chunks`] system
 pub fn should_update_view_chunks(
-    view_distance: &Res<ChunkLoadRadius>,
-    player_pos: &Res<CurrentLocalPlayerChunk>,
+    changed_viewers: &Query<
+        (),
+        (With<ChunkViewer>, Or<(Changed<ChunkViewer>, Changed<Position>)>),
+    >,
+    removed_viewers: &RemovedComponents<ChunkViewer>,
 ) -> bool {
-    player_pos.is_changed()
-        || view_distance.is_changed()
-        || player_pos.is_added()
-        || view_distance.is_added()
+    !changed_viewers.is_empty() || removed_viewers.iter().next().is_some()
 }
 
-/// Checks for the loaded chunks around the player and schedules loading of new chunks in sight
+/// Checks for the loaded chunks around every viewer and schedules loading of new chunks in
+/// sight. Chunks are reference-counted by the viewers requiring them, and unloaded once no
+/// viewer keeps them in range.
 pub fn update_view_chunks(
-    view_radius: Res<ChunkLoadRadius>,
+    viewers: Query<(&ChunkViewer, &Position)>,
+    changed_viewers: Query<
+        (),
+        (With<ChunkViewer>, Or<(Changed<ChunkViewer>, Changed<Position>)>),
+    >,
+    removed_viewers: RemovedComponents<ChunkViewer>,
     chunk_entities: Res<ChunkEntities>,
-    player_pos: Res<CurrentLocalPlayerChunk>,
+    mut chunk_refs: ResMut<ChunkViewerRefs>,
     mut chunk_command_queue: ResMut<ChunkCommandQueue>,
 ) {
-    if !should_update_view_chunks(&view_radius, &player_pos) {
+    if !should_update_view_chunks(&changed_viewers, &removed_viewers) {
         return;
     }
 
-    // quick n dirty circular chunk loading.
-    //perf: optimize this.
-    for x in -view_radius.horizontal..=view_radius.horizontal {
-        for y in -view_radius.vertical..=view_radius.vertical {
-            if (x < 0 || y < 0) || x.pow(2) + y.pow(2) >= view_radius.horizontal.pow(2) {
-                continue;
-            }
+    let viewers = viewers
+        .iter()
+        .map(|(viewer, pos)| (*viewer, pos.chunk_location().as_ivec2()))
+        .collect::<Vec<_>>();
+
+    // Reference count the chunks required by each viewer, keeping the best priority
+    let mut required_chunks: HashMap<ChunkLocation, (usize, i32)> = HashMap::default();
+    for (viewer, viewer_chunk) in viewers.iter() {
+        let extents = viewer.extents();
+        for x in -extents.x..=extents.x {
+            for y in -extents.y..=extents.y {
+                if !viewer.should_load(IVec2::new(x, y)) {
+                    continue;
+                }
 
-            let chunk_key = {
-                let mut pos: IVec2 = player_pos.chunk_min
-                    + IVec2::new(x * CHUNK_SIZE as i32, y * CHUNK_SIZE as i32);
-                pos.x = pos.x.max(0);
-                pos
-            };
+                let chunk_key: IVec2 =
+                    *viewer_chunk + IVec2::new(x * CHUNK_SIZE_I32, y * CHUNK_SIZE_I32);
 
-            let loc: ChunkLocation = chunk_key.into();
-            println!("chunk key: {loc:?}");
-            if chunk_entities.entity(loc).is_none()
-                && !chunk_command_queue.create.contains(&loc)
-                && !chunk_command_queue.destroy.contains(&loc)
-            {
-                chunk_command_queue.create.push(loc);
+                let loc = ChunkLocation::from(chunk_key).wrapped();
+                let priority = chunk_priority(loc, *viewer_chunk);
+                let entry = required_chunks.entry(loc).or_insert((0, priority));
+                entry.0 += 1;
+                entry.1 = entry.1.min(priority);
             }
         }
     }
 
-    // quick n dirty circular chunk !loading.
+    for (loc, (_, priority)) in required_chunks.iter() {
+        if let Some(idx) = chunk_command_queue.destroy.iter().position(|l| l == loc) {
+            // Back in range before the unload ran, keep the chunk around.
+            chunk_command_queue.destroy.swap_remove(idx);
+        } else if chunk_entities.entity(*loc).is_none()
+            && !chunk_command_queue.create.contains(loc)
+        {
+            chunk_command_queue.create.push(*loc, *priority);
+        }
+    }
+
+    // Chunks are kept until they leave every viewer's shape grown by its unload margin
     for loaded_chunk in chunk_entities.0.keys() {
-        let delta: IVec2 = loaded_chunk.as_ivec2() - player_pos.chunk_min;
-        if delta.x.pow(2) >= view_radius.horizontal.pow(2) * (CHUNK_SIZE as i32).pow(2)
-            || delta.y.pow(2) >= view_radius.vertical.pow(2) * (CHUNK_SIZE as i32).pow(2)
+        let in_range = viewers.iter().any(|(viewer, viewer_chunk)| {
+            let delta: IVec2 = loaded_chunk.nearest_to(viewer_chunk.x) - *viewer_chunk;
+            viewer.should_keep(delta / CHUNK_SIZE_I32)
+        });
+
+        if !in_range
+            && !required_chunks.contains_key(loaded_chunk)
+            && !chunk_command_queue.destroy.contains(loaded_chunk)
         {
             chunk_command_queue.destroy.push(*loaded_chunk);
         }
     }
 
-    // load chunks starting from the player position
-    chunk_command_queue.create.sort_unstable_by_key(|key| {
-        FloatOrd(key.as_vec2().distance(player_pos.chunk_min.as_vec2()))
-    });
+    // drop queued chunks that fell out of range before they were created
+    chunk_command_queue.create.retain(|loc| required_chunks.contains_key(loc));
+
+    // load chunks starting from the closest viewer
+    chunk_command_queue.create.reprioritize(|loc| required_chunks[loc].1);
+
+    chunk_refs.0 = required_chunks.into_iter().map(|(loc, (count, _))| (loc, count)).collect();
+}
+
+/// Priority of a queued chunk creation, the squared distance to the viewer's chunk.
+fn chunk_priority(loc: ChunkLocation, viewer_chunk: IVec2) -> i32 {
+    let delta = loc.nearest_to(viewer_chunk.x) - viewer_chunk;
+    delta.dot(delta)
 }
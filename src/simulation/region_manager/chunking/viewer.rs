@@ -0,0 +1,168 @@
+use crate::prelude::*;
+use bevy::utils::HashMap;
+
+/// The distance, in chunks, around a viewer in which chunks are loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkLoadRadius {
+    pub horizontal: i32,
+    pub vertical: i32,
+}
+
+impl Default for ChunkLoadRadius {
+    fn default() -> Self { Self { horizontal: 4, vertical: 4 } }
+}
+
+/// The area around a viewer in which chunks are loaded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChunkLoadShape {
+    /// A circle using the horizontal radius.
+    #[default]
+    Circle,
+    /// An ellipse using both the horizontal and vertical radius.
+    Ellipse,
+    /// A rectangle using both the horizontal and vertical radius.
+    Square,
+    /// A rectangle covering the camera viewport. The radius is ignored.
+    Viewport,
+}
+
+/// A component keeping the chunks around an entity's [`Position`] loaded. Attach it to
+/// anything that needs the world around it simulated: the player, NPC caravans, or an
+/// anchor entity that keeps an area loaded.
+///
+/// Chunks are loaded within the shape, and only unloaded once they are `unload_margin`
+/// chunks outside of it, so moving back and forth across the edge doesn't reload them.
+#[derive(Debug, Clone, Copy, Component, Serialize, Deserialize)]
+pub struct ChunkViewer {
+    pub radius: ChunkLoadRadius,
+    pub shape: ChunkLoadShape,
+    pub unload_margin: i32,
+}
+
+impl Default for ChunkViewer {
+    fn default() -> Self {
+        Self {
+            radius: ChunkLoadRadius::default(),
+            shape: ChunkLoadShape::Circle,
+            unload_margin: 1,
+        }
+    }
+}
+
+impl ChunkViewer {
+    pub fn new(radius: ChunkLoadRadius, shape: ChunkLoadShape) -> Self {
+        Self { radius, shape, ..Default::default() }
+    }
+
+    /// Half extents, in chunks, of the box bounding the loaded area.
+    pub fn extents(&self) -> IVec2 {
+        match self.shape {
+            ChunkLoadShape::Circle => IVec2::splat(self.radius.horizontal),
+            ChunkLoadShape::Ellipse | ChunkLoadShape::Square => {
+                IVec2::new(self.radius.horizontal, self.radius.vertical)
+            }
+            // The viewer is anywhere within its chunk, so pad the viewport by one chunk
+            ChunkLoadShape::Viewport => IVec2::new(
+                (VIEWPORT_X_OFFSET + CHUNK_SIZE_I32 - 1) / CHUNK_SIZE_I32 + 1,
+                (VIEWPORT_Y_OFFSET + CHUNK_SIZE_I32 - 1) / CHUNK_SIZE_I32 + 1,
+            ),
+        }
+    }
+
+    /// Returns true if a chunk `delta` chunks away from the viewer's chunk should be loaded.
+    pub fn should_load(&self, delta: IVec2) -> bool { self.contains(delta, 0) }
+
+    /// Returns true if a loaded chunk `delta` chunks away from the viewer's chunk should stay
+    /// loaded.
+    pub fn should_keep(&self, delta: IVec2) -> bool {
+        self.contains(delta, self.unload_margin)
+    }
+
+    fn contains(&self, delta: IVec2, margin: i32) -> bool {
+        let extents = self.extents() + IVec2::splat(margin);
+        match self.shape {
+            ChunkLoadShape::Circle => delta.dot(delta) <= extents.x.pow(2),
+            ChunkLoadShape::Ellipse => {
+                let (h, v) = (extents.x.max(1).pow(2), extents.y.max(1).pow(2));
+                delta.x.pow(2) * v + delta.y.pow(2) * h <= h * v
+            }
+            ChunkLoadShape::Square | ChunkLoadShape::Viewport => {
+                delta.x.abs() <= extents.x && delta.y.abs() <= extents.y
+            }
+        }
+    }
+}
+
+/// The number of viewers requiring each chunk. Chunks without a viewer are unloaded.
+#[derive(Debug, Default)]
+pub struct ChunkViewerRefs(pub HashMap<ChunkLocation, usize>);
+
+impl ChunkViewerRefs {
+    /// Returns the number of viewers requiring the chunk.
+    pub fn count(&self, chunk: ChunkLocation) -> usize {
+        self.0.get(&chunk).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of chunks required by at least one viewer.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_loads_negative_offsets() {
+        let viewer = ChunkViewer::default();
+        assert!(viewer.should_load(IVec2::new(-2, -3)));
+        assert!(viewer.should_load(IVec2::new(-4, 0)));
+        assert!(!viewer.should_load(IVec2::new(-4, -1)));
+    }
+
+    #[test]
+    fn test_vertical_radius() {
+        let radius = ChunkLoadRadius { horizontal: 4, vertical: 2 };
+
+        // Only the circle ignores the vertical radius
+        let circle = ChunkViewer::new(radius, ChunkLoadShape::Circle);
+        assert!(circle.should_load(IVec2::new(0, -4)));
+
+        let ellipse = ChunkViewer::new(radius, ChunkLoadShape::Ellipse);
+        assert!(ellipse.should_load(IVec2::new(-4, 0)));
+        assert!(ellipse.should_load(IVec2::new(0, 2)));
+        assert!(ellipse.should_load(IVec2::new(2, -1)));
+        assert!(!ellipse.should_load(IVec2::new(0, -3)));
+        assert!(!ellipse.should_load(IVec2::new(3, 2)));
+        assert!(ellipse.should_keep(IVec2::new(0, -3)));
+
+        let square = ChunkViewer::new(radius, ChunkLoadShape::Square);
+        assert!(square.should_load(IVec2::new(-4, 2)));
+        assert!(!square.should_load(IVec2::new(0, 3)));
+    }
+
+    #[test]
+    fn test_keeps_loaded_chunks_within_margin() {
+        for shape in [
+            ChunkLoadShape::Circle,
+            ChunkLoadShape::Ellipse,
+            ChunkLoadShape::Square,
+            ChunkLoadShape::Viewport,
+        ] {
+            let viewer =
+                ChunkViewer::new(ChunkLoadRadius { horizontal: 4, vertical: 2 }, shape);
+            let extents = viewer.extents();
+            for x in -extents.x - 2..=extents.x + 2 {
+                for y in -extents.y - 2..=extents.y + 2 {
+                    let delta = IVec2::new(x, y);
+                    if viewer.should_load(delta) {
+                        assert!(viewer.should_keep(delta), "{:?} {:?}", shape, delta);
+                    }
+                }
+            }
+            assert!(!viewer.should_load(extents + IVec2::X));
+            assert!(viewer.should_keep(IVec2::new(extents.x + 1, 0)));
+        }
+    }
+}
@@ -2,10 +2,14 @@ mod chunking;
 mod queries;
 mod region_chunk_applicator;
 mod region_chunk_populator;
+mod region_eviction;
 mod region_loader;
+mod region_streaming;
 
 pub use chunking::*;
 pub use queries::*;
 pub use region_chunk_applicator::*;
 pub use region_chunk_populator::*;
+pub use region_eviction::*;
 pub use region_loader::*;
+pub use region_streaming::*;
@@ -1,31 +1,21 @@
 use crate::prelude::*;
+use std::collections::BTreeMap;
 
-/// Returns true when a region has reached the "TilesCreated" stage---it
-/// can be queried for tile content. Prettying hasn't occurred yet,
-/// neither has render creation.
-pub fn is_region_loaded(location: PlanetLocation) -> bool {
-    let index = location.to_region_index();
-    let region_lock = REGIONS.read();
-    if let Some(region) = region_lock.regions.get(&index) {
-        region.status == RegionStatus::CreatedTiles
-    } else {
-        false
-    }
+/// The lifecycle status of a region. Regions that were never requested are `NotLoaded`.
+pub fn region_status(location: PlanetLocation) -> RegionStatus {
+    REGIONS
+        .read()
+        .get_region(location.to_region_index())
+        .map_or(RegionStatus::NotLoaded, |region| region.status)
 }
 
-/// Checks a set of regions for "TileCreated" status---ready to be queried.
-/// Prettying hasn't occurred, neither has render creation.
-pub fn are_regions_loaded(locations: &[PlanetLocation]) -> bool {
-    let mut loaded = true;
-    let region_lock = REGIONS.read();
-    locations.iter().map(|loc| loc.to_region_index()).for_each(|idx| {
-        if let Some(region) = region_lock.regions.get(&idx) {
-            if region.status != RegionStatus::CreatedTiles {
-                loaded = false;
-            }
-        }
+/// How many regions are at each status, for the regions that were ever requested.
+pub fn count_regions_by_status() -> BTreeMap<RegionStatus, usize> {
+    let mut counts = BTreeMap::new();
+    REGIONS.read().regions.values().for_each(|region| {
+        *counts.entry(region.status).or_insert(0) += 1;
     });
-    loaded
+    counts
 }
 
 // /// Returns true if a tile is a floor or has a solid tile underneath it.
@@ -42,9 +32,59 @@ pub fn are_regions_loaded(locations: &[PlanetLocation]) -> bool {
 pub fn get_material_idx(region_id: PlanetLocation, tile_idx: usize) -> usize {
     let index = region_id.to_region_index();
     let region_lock = REGIONS.read();
-    if let Some(region) = region_lock.regions.get(&index) {
-        region.material[tile_idx]
-    } else {
-        0
+    match region_lock.regions.get(&index) {
+        // Evicted regions have no tiles in memory
+        Some(region) if region.status.is_ready() => region.material[tile_idx],
+        _ => 0,
+    }
+}
+
+/// Height of the ground under a world-space tile, on the same scale as
+/// [`Planet::water_height`]. Loaded chunks are read first, since their region may have been
+/// evicted. Returns `None` if neither the tile's chunk nor its region is in memory.
+pub fn elevation_at(
+    elevations: Option<&ChunkMap<u32, ChunkShape>>,
+    world_pos: IVec2,
+) -> Option<u32> {
+    let pos = Position::from_world(world_pos);
+    if !pos.region.is_on_planet() {
+        return None;
+    }
+
+    if let Some(elevation) = elevations.and_then(|elevations| elevations.get_tile(world_pos)) {
+        return Some(elevation);
+    }
+
+    let region_lock = REGIONS.read();
+    match region_lock.get_region(pos.region.to_region_index()) {
+        Some(region) if region.status.is_ready() => {
+            Some(region.elevation[pos.to_tile_index()])
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_elevation_of_loaded_chunk_in_evicted_region() {
+        setup_test_planet();
+        let location = PlanetLocation::new(IVec2::new(16, 8));
+        spawn_playable_region(location);
+        populate_region(location);
+
+        let world = location.to_world() + IVec2::new(3, 5);
+        let expected = elevation_at(None, world).unwrap();
+
+        let mut elevations = ChunkMap::<u32, ChunkShape>::new(ChunkShape {});
+        let chunk = build_chunk(ChunkLocation::from(location.to_world()));
+        let minimum = chunk.location.as_ivec2();
+        elevations.insert(minimum, chunk.into_buffers().1);
+
+        REGIONS.write().get_region_mut(location.to_region_index()).unwrap().evict();
+        assert_eq!(elevation_at(None, world), None);
+        assert_eq!(elevation_at(Some(&elevations), world), Some(expected));
     }
 }
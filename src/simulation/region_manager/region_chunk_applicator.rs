@@ -1,33 +1,43 @@
 use crate::prelude::*;
 use futures_lite::future;
 
+/// Copies a populated chunk into its region. Once every chunk of the region is in, the
+/// region is ready.
+pub fn apply_region_chunk(region: &mut Region, chunk: &RegionChunkPopulator) {
+    let chunk_x = chunk.chunk_id.x as usize / CHUNK_SIZE;
+    let chunk_y = chunk.chunk_id.y as usize / CHUNK_SIZE;
+    let chunk_id = (chunk_y * CHUNK_WIDTH) + chunk_x;
+
+    ChunkIterator::new(chunk.chunk_id).enumerate().for_each(|(idx, chunk_idx)| {
+        region.tiles[chunk_idx.to_tile_index()] = chunk.tiles[idx];
+        region.material[chunk_idx.to_tile_index()] = chunk.material[idx];
+        region.elevation[chunk_idx.to_tile_index()] = chunk.elevation[idx];
+    });
+
+    region.chunks_loaded[chunk_id] = true;
+    if region.chunks_loaded.iter().filter(|l| **l).count() == CHUNKS_PER_REGION {
+        region.status = RegionStatus::Ready;
+    }
+}
+
 /// Applies the result of building individual region chunks
 pub fn region_tile_applicator_system(
     mut commands: Commands,
     mut region_loaders: Query<(Entity, &mut RegionLoaderTask)>,
 ) {
     for (task_entity, mut task) in region_loaders.iter_mut() {
-        if let Some(chunk) = future::block_on(future::poll_once(&mut task.0)) {
-            let mut region_lock = REGIONS.write();
-            let region_id = chunk.region_id;
-
-            if let Some(region) = region_lock.regions.get_mut(&region_id) {
-                let chunk_x = chunk.chunk_id.x / CHUNK_SIZE;
-                let chunk_y = chunk.chunk_id.y / CHUNK_SIZE;
-                let chunk_id = (chunk_y * CHUNK_WIDTH) + chunk_x;
-
-                ChunkIterator::new(chunk.chunk_id).enumerate().for_each(|(idx, chunk_idx)| {
-                    region.tiles[chunk_idx.to_tile_index()] = chunk.tiles[idx];
-                    region.material[chunk_idx.to_tile_index()] = chunk.material[idx];
-                });
+        // Chunks still being populated are checked again next frame
+        let chunk = match future::block_on(future::poll_once(&mut task.0)) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
 
-                region.chunks_loaded[chunk_id] = true;
-                if region.chunks_loaded.iter().filter(|l| **l).count() == CHUNKS_PER_REGION {
-                    region.status = RegionStatus::CreatedTiles;
-                }
+        // The region may have been evicted or restored from the save while the chunk was
+        // being populated, in which case the chunk is no longer wanted
+        if let Some(region) = REGIONS.write().get_region_mut(chunk.region_id) {
+            if region.status == RegionStatus::Generating {
+                apply_region_chunk(region, &chunk);
             }
-        } else {
-            panic!("Received region chunk data for a non-loaded region");
         }
 
         // Remove the task now that it's done
@@ -1,15 +1,14 @@
 use crate::prelude::*;
-use bracket_random::prelude::RandomNumberGenerator;
 
-/// Provides an async interface to filling the tile types/materials/etc.
-/// for a chunk from noise (does *not* include subsequent changes or)
-/// ramps.
+/// Provides an async interface to filling the tile types/materials/elevation/vegetation/etc.
+/// for a chunk from noise (does *not* include subsequent changes).
 /// Used by the region loader to break tiling into multiple processes.
 pub struct RegionChunkPopulator {
     pub region_id: usize,
     pub material: Vec<usize>,
     pub revealed: Vec<bool>,
     pub tiles: Vec<TileType>,
+    pub elevation: Vec<u32>,
     pub chunk_id: ChunkLocation,
 }
 
@@ -21,6 +20,7 @@ impl RegionChunkPopulator {
             material: vec![0; TILES_PER_CHUNK],
             revealed: vec![false; TILES_PER_CHUNK],
             tiles: vec![TileType::Floor; TILES_PER_CHUNK],
+            elevation: vec![0; TILES_PER_CHUNK],
         }
     }
 }
@@ -38,64 +38,126 @@ pub fn populate_region_chunk(
     let noise = plock.height_noise.as_ref().unwrap();
     let cell_noise = plock.material_noise.as_ref().unwrap();
 
-    let tile_x = region_id % WORLD_WIDTH;
-    let tile_y = region_id / WORLD_WIDTH;
-    let biome_idx = planet.landblocks[region_id].biome_idx;
-    let biome = &RAWS.read().biomes.areas[biome_idx];
-
-    // Determine base altitudes for the region
-    let mut altitudes = vec![0; CHUNK_SIZE * CHUNK_SIZE];
-    for y in 0..CHUNK_SIZE {
-        for x in 0..CHUNK_SIZE {
-            let altitude =
-                cell_altitude(noise, tile_x, tile_y, x + chunk_id.x, y + chunk_id.y);
-            let altitude_idx = (y * CHUNK_SIZE) + x;
-            altitudes[altitude_idx] = altitude;
-        }
-    }
+    let raws = RAWS.read();
+    let mean_temperature = planet.landblocks[region_id].temperature_c as i8;
 
-    // let max_altitude = *altitudes.iter().max().unwrap() as usize;
+    // Everything below is sampled in world space, so chunks line up with the chunks of
+    // neighboring regions whatever order they are generated in
+    let (region_x, region_y) = idx_planet(region_id);
+    let region_origin = PlanetLocation::new(IVec2::new(region_x as i32, region_y as i32));
+    let chunk_origin = region_origin.to_world() + chunk_id.as_ivec2();
 
-    // Build a local RNG
-    let mut rng = RandomNumberGenerator::seeded(
-        planet.noise_seed
-            + ((tile_y * REGION_WIDTH * CHUNKS_PER_REGION)
-                + (tile_x * REGION_WIDTH * CHUNK_WIDTH)
-                + (chunk_id.y * CHUNK_SIZE)
-                + chunk_id.x) as u64,
-    );
+    // Determine base altitudes for the chunk, with a one tile border so slopes at the chunk
+    // edges see the tiles of the neighboring chunks
+    const BORDERED: usize = CHUNK_SIZE + 2;
+    let mut altitudes = vec![0; BORDERED * BORDERED];
+    for y in 0..BORDERED {
+        for x in 0..BORDERED {
+            let world = chunk_origin + IVec2::new(x as i32 - 1, y as i32 - 1);
+            altitudes[(y * BORDERED) + x] = cell_altitude(noise, world);
+        }
+    }
+    let altitude =
+        |x: i32, y: i32| altitudes[((y + 1) as usize * BORDERED) + (x + 1) as usize];
 
     for cy in 0..CHUNK_SIZE {
-        let ry = cy + chunk_id.y;
         for cx in 0..CHUNK_SIZE {
-            let rx = cx + chunk_id.x;
-            // let altitude_idx = (cy * CHUNK_SIZE) + cx;
-            // let altitude = altitudes[altitude_idx] as usize;
-
+            let world = chunk_origin + IVec2::new(cx as i32, cy as i32);
             let chunk_idx = chunk_idx(cx, cy);
+            let elevation = altitude(cx as i32, cy as i32);
+            result.elevation[chunk_idx] = elevation;
 
             // Soil or sand
-            let n = cell_noise.get_noise(noise_lon(tile_y, ry * 2), noise_lat(tile_x, rx * 2));
+            let (nx, ny, nz) = world_tile_sphere(world, DEGREE_SPHERE_RADIUS);
+            let n = cell_noise.get_noise3d(nx, ny, nz);
 
-            if rng.roll_dice(1, 100) < biome.soils.soil {
+            let (soil_chance, sand_chance) = soil_chances(planet, &raws, world);
+            let mut rng = tile_rng(planet.noise_seed, world, TileRoll::Soil);
+            if (rng.roll_dice(1, 100) as f32) < soil_chance {
                 result.tiles[chunk_idx] = TileType::Soil;
                 result.material[chunk_idx] = pick_material(&strata.soils, n);
-            } else if rng.roll_dice(1, 100) < biome.soils.sand {
+            } else if (rng.roll_dice(1, 100) as f32) < sand_chance {
                 result.tiles[chunk_idx] = TileType::Sand;
                 result.material[chunk_idx] = pick_material(&strata.sand, n);
             }
+
+            // Water, cliffs and ramps cover the ground, which keeps its material
+            let rise = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .map(|(dx, dy)| altitude(cx as i32 + dx, cy as i32 + dy))
+                .max()
+                .unwrap_or(elevation)
+                .saturating_sub(elevation);
+            if let Some(tile) = terrain_tile(elevation, rise, planet.water_height) {
+                result.tiles[chunk_idx] = tile;
+            }
+
+            // Vegetation, then trees on the bare floor left
+            if result.tiles[chunk_idx] == TileType::Floor {
+                let mut rng = tile_rng(planet.noise_seed, world, TileRoll::Plant);
+                let material = result.material[chunk_idx];
+                if let Some(plant) = pick_plant(&mut rng, &raws, material, mean_temperature) {
+                    result.tiles[chunk_idx] = plant;
+                }
+            }
+            if result.tiles[chunk_idx] == TileType::Floor {
+                let mut rng = tile_rng(planet.noise_seed, world, TileRoll::Tree);
+                if let Some(tree) = pick_tree(&mut rng) {
+                    result.tiles[chunk_idx] = tree;
+                }
+            }
         }
     }
 
     result
 }
 
-fn cell_altitude(noise: &FastNoise, tile_x: usize, tile_y: usize, x: usize, y: usize) -> u32 {
-    let lat = noise_lat(tile_y, y);
-    let lon = noise_lon(tile_x, x);
-    let sphere_coords = sphere_vertex(100.0, Degrees::new(lat), Degrees::new(lon));
-    let noise_height = noise.get_noise3d(sphere_coords.0, sphere_coords.1, sphere_coords.2);
-    noise_to_planet_height(noise_height)
+/// Chances out of 100 of soil and of sand at a world tile. Each landblock's biome sets the
+/// chances at its center, tiles in between blend the four nearest landblocks, so the ground
+/// changes gradually across region borders.
+fn soil_chances(planet: &Planet, raws: &Raws, world: IVec2) -> (f32, f32) {
+    let fx = world.x as f32 / REGION_WIDTH as f32 - 0.5;
+    let fy = world.y as f32 / REGION_HEIGHT as f32 - 0.5;
+    let (x0, y0) = (fx.floor(), fy.floor());
+    let (tx, ty) = (fx - x0, fy - y0);
+
+    // The planet wraps around east to west, but not past the poles
+    let chances = |dx: i32, dy: i32| {
+        let x = (x0 as i32 + dx).rem_euclid(WORLD_WIDTH as i32) as usize;
+        let y = (y0 as i32 + dy).clamp(0, WORLD_HEIGHT as i32 - 1) as usize;
+        let soils = &raws.biomes.areas[planet.landblocks[planet_idx(x, y)].biome_idx].soils;
+        (soils.soil as f32, soils.sand as f32)
+    };
+    let lerp =
+        |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+
+    let top = lerp(chances(0, 0), chances(1, 0), tx);
+    let bottom = lerp(chances(0, 1), chances(1, 1), tx);
+    lerp(top, bottom, ty)
+}
+
+/// Height of the terrain at a world tile.
+pub fn cell_altitude(noise: &FastNoise, world: IVec2) -> u32 {
+    let (x, y, z) = world_tile_sphere(world, 100.0);
+    noise_to_planet_height(noise.get_noise3d(x, y, z))
+}
+
+/// Tiles rising this much above a neighbor are too steep to walk up, and become cliffs.
+const CLIFF_HEIGHT: u32 = 2;
+
+/// The tile shaped by the terrain at `elevation`, `rise` being the height of the highest
+/// neighbor above it. Tiles under the water line are flooded, and tiles at the foot of a
+/// slope become a ramp up it, or a cliff wall when it is too steep.
+pub fn terrain_tile(elevation: u32, rise: u32, water_height: u32) -> Option<TileType> {
+    if elevation <= water_height {
+        Some(TileType::Water)
+    } else if rise >= CLIFF_HEIGHT {
+        Some(TileType::Wall)
+    } else if rise > 0 {
+        Some(TileType::Ramp)
+    } else {
+        None
+    }
 }
 
 fn pick_material(materials: &[usize], noise: f32) -> usize {
@@ -103,3 +165,43 @@ fn pick_material(materials: &[usize], noise: f32) -> usize {
     let n = materials.len() as f32 / 1.0;
     materials[(noise_normalized * n) as usize]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_terrain_tiles() {
+        assert_eq!(terrain_tile(90, 0, 100), Some(TileType::Water));
+        assert_eq!(terrain_tile(100, 5, 100), Some(TileType::Water));
+        assert_eq!(terrain_tile(120, 0, 100), None);
+        assert_eq!(terrain_tile(120, 1, 100), Some(TileType::Ramp));
+        assert_eq!(terrain_tile(120, CLIFF_HEIGHT, 100), Some(TileType::Wall));
+    }
+
+    #[test]
+    fn test_soil_chances_blend_across_regions() {
+        setup_test_planet();
+        let raws = RAWS.read();
+        let chances = |biome: &Biome| (biome.soils.soil as f32, biome.soils.sand as f32);
+        let first = chances(&raws.biomes.areas[0]);
+        let other =
+            raws.biomes.areas.iter().position(|biome| chances(biome) != first).unwrap();
+
+        // The regions at x 1 take a biome with other soils
+        let mut planet = test_planet();
+        for y in 0..WORLD_HEIGHT {
+            planet.landblocks[planet_idx(1, y)].biome_idx = other;
+        }
+
+        let (width, height) = (REGION_WIDTH as i32, REGION_HEIGHT as i32);
+        let y = 10 * height + height / 2;
+        let second = chances(&raws.biomes.areas[other]);
+        assert_eq!(soil_chances(&planet, &raws, IVec2::new(width / 2, y)), first);
+        assert_eq!(soil_chances(&planet, &raws, IVec2::new(width + width / 2, y)), second);
+
+        // Both biomes count as much on the border between their regions
+        let border = soil_chances(&planet, &raws, IVec2::new(width, y));
+        assert_eq!(border, ((first.0 + second.0) / 2.0, (first.1 + second.1) / 2.0));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// The generated tiles of a region, saved before the region is evicted from memory.
+#[derive(Serialize, Deserialize)]
+pub struct RegionSave {
+    pub location: PlanetLocation,
+    #[serde(with = "paletted_tiles")]
+    pub tiles: Vec<TileType>,
+    #[serde(with = "paletted_tiles")]
+    pub material: Vec<usize>,
+    #[serde(with = "paletted_tiles")]
+    pub elevation: Vec<u32>,
+}
+
+// Region saves only cache generated tiles, older versions fail to load and the region is
+// generated again instead of being migrated
+impl SavePayload for RegionSave {
+    const PAYLOAD: PayloadType = PayloadType::Region;
+    const VERSION: u32 = 2;
+}
+
+/// Saves the tiles of a region so it can be evicted, and marks it as persisted.
+pub fn persist_region(slot: &SaveSlot, region: &mut Region) -> Result<(), IOError> {
+    let save = RegionSave {
+        location: region.location,
+        tiles: region.tiles.clone(),
+        material: region.material.clone(),
+        elevation: region.elevation.clone(),
+    };
+    save_data(slot.region_path(region.location), save)?;
+
+    region.status = RegionStatus::Persisted;
+    Ok(())
+}
+
+/// Reads the tiles of an evicted region back from its save.
+pub fn restore_region(slot: &SaveSlot, region: &mut Region) -> Result<(), IOError> {
+    let save = load_data::<RegionSave>(slot.region_path(region.location))?;
+    let lengths = [save.tiles.len(), save.material.len(), save.elevation.len()];
+    if lengths.iter().any(|len| *len != REGION_TILES_COUNT) {
+        return Err(IOError::SaveFileCorrupted.in_file(slot.region_path(region.location)));
+    }
+
+    region.tiles = save.tiles;
+    region.material = save.material;
+    region.elevation = save.elevation;
+    region.chunks_loaded = vec![true; CHUNKS_PER_REGION];
+    region.status = RegionStatus::Persisted;
+    Ok(())
+}
+
+/// Upper bound on the memory held by the tiles of regions in [`REGIONS`]. Past it, regions no
+/// viewer needs are saved and evicted, farthest first.
+pub struct RegionMemoryBudget {
+    pub max_bytes: usize,
+}
+
+impl Default for RegionMemoryBudget {
+    fn default() -> Self { Self { max_bytes: 16 * 1024 * 1024 } }
+}
+
+/// Evicts the regions that aren't near any [`ChunkViewer`] while the regions in memory go
+/// over the [`RegionMemoryBudget`].
+pub fn evict_regions(
+    slot: Res<SaveSlot>,
+    budget: Res<RegionMemoryBudget>,
+    viewers_q: Query<&Position, With<ChunkViewer>>,
+) {
+    let mut memory = REGIONS.read().regions.values().map(Region::heap_size).sum::<usize>();
+    if memory <= budget.max_bytes {
+        return;
+    }
+
+    let viewers = viewers_q.iter().map(|pos| pos.region).collect::<Vec<_>>();
+    let needed = viewers_q.iter().flat_map(regions_near).collect::<HashSet<_>>();
+    let distance = |location: PlanetLocation| {
+        viewers.iter().map(|viewer| (viewer.0 - location.0).abs().max_element()).min()
+    };
+
+    let mut region_lock = REGIONS.write();
+    let mut candidates = region_lock
+        .regions
+        .values()
+        .filter(|region| region.status.is_ready() && !needed.contains(&region.location))
+        .map(|region| region.location)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|location| std::cmp::Reverse(distance(*location)));
+
+    for location in candidates {
+        if memory <= budget.max_bytes {
+            break;
+        }
+
+        let region = match region_lock.get_region_mut(location.to_region_index()) {
+            Some(region) => region,
+            None => continue,
+        };
+        if region.status == RegionStatus::Ready {
+            if let Err(err) = persist_region(&slot, region) {
+                println!("Failed to save region: {}", err.report());
+                continue;
+            }
+        }
+
+        memory -= region.heap_size();
+        region.evict();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_evicted_region_is_restored() {
+        let slot = SaveSlot::new("region eviction");
+        let mut region = Region::new(PlanetLocation::new(IVec2::new(4, 2)));
+        region.tiles[7] = TileType::Water;
+        region.material[7] = 3;
+        region.elevation[7] = 120;
+        region.status = RegionStatus::Ready;
+        let size = region.heap_size();
+
+        persist_region(&slot, &mut region).unwrap();
+        region.evict();
+        assert_eq!(region.status, RegionStatus::Evicted);
+        assert!(region.heap_size() < size / 100);
+
+        restore_region(&slot, &mut region).unwrap();
+        assert_eq!(region.status, RegionStatus::Persisted);
+        assert_eq!((region.tiles[7], region.material[7]), (TileType::Water, 3));
+        assert_eq!(region.elevation[7], 120);
+    }
+}
@@ -26,7 +26,7 @@ pub fn load_regions(mut commands: Commands) {
     for (region_id, region) in region_lock.regions.iter_mut() {
         if region.status == RegionStatus::NotLoaded {
             // Spawn a region loader task
-            region.status = RegionStatus::CreatingTiles;
+            region.status = RegionStatus::Generating;
 
             AllChunksIterator::new().for_each(|chunk_base| {
                 let region = *region_id; // Copy to ensure we have a local to move
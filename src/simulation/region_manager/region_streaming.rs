@@ -0,0 +1,129 @@
+use crate::prelude::*;
+
+/// How close to a region edge, in tiles, the player gets before the region past the edge is
+/// generated.
+pub const REGION_STREAM_DISTANCE: i32 = 64;
+
+/// Queues the regions around the player for generation as they approach a region edge.
+/// Queued regions are picked up by [`load_regions`] and filled in by
+/// [`region_tile_applicator_system`]. Regions that were evicted are read back from the save.
+pub fn stream_regions(
+    slot: Res<SaveSlot>,
+    player_q: Query<&Position, (With<Player>, Changed<Position>)>,
+) {
+    let pos = match player_q.get_single() {
+        Ok(pos) => pos,
+        Err(_) => return,
+    };
+
+    let wanted = regions_near(pos)
+        .into_iter()
+        .filter(|location| {
+            matches!(region_status(*location), RegionStatus::NotLoaded | RegionStatus::Evicted)
+        })
+        .collect::<Vec<_>>();
+
+    if wanted.is_empty() {
+        return;
+    }
+
+    let mut region_lock = REGIONS.write();
+    for location in wanted {
+        let region = region_lock
+            .regions
+            .entry(location.to_region_index())
+            .or_insert_with(|| Region::new(location));
+
+        if region.status == RegionStatus::Evicted {
+            if let Err(err) = restore_region(&slot, region) {
+                println!("Failed to restore region, generating it again: {}", err.report());
+                *region = Region::new(location);
+            }
+        }
+    }
+}
+
+/// The region of the position, and the neighboring regions whose edge is within
+/// [`REGION_STREAM_DISTANCE`] tiles. Regions wrap around the east/west edge of the planet,
+/// there is nothing past the poles.
+pub fn regions_near(pos: &Position) -> Vec<PlanetLocation> {
+    let steps = |tile: i32, size: usize| {
+        let mut steps = vec![0];
+        if tile < REGION_STREAM_DISTANCE {
+            steps.push(-1);
+        }
+        if tile >= size as i32 - REGION_STREAM_DISTANCE {
+            steps.push(1);
+        }
+        steps
+    };
+
+    let mut regions = Vec::new();
+    for dy in steps(pos.tile.y, REGION_HEIGHT) {
+        let y = pos.region.y + dy;
+        if !(0..WORLD_HEIGHT as i32).contains(&y) {
+            continue;
+        }
+
+        for dx in steps(pos.tile.x, REGION_WIDTH) {
+            let x = (pos.region.x + dx).rem_euclid(WORLD_WIDTH as i32);
+            regions.push(PlanetLocation::new(IVec2::new(x, y)));
+        }
+    }
+
+    regions
+}
+
+pub struct RegionStreamingPlugin;
+impl Plugin for RegionStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegionMemoryBudget>().add_system_set(
+            ConditionSet::new()
+                .run_in_state(GameState::InGame)
+                .with_system(stream_regions)
+                .with_system(load_regions)
+                .with_system(region_tile_applicator_system)
+                .with_system(evict_regions)
+                .into(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn location(x: i32, y: i32) -> PlanetLocation { PlanetLocation::new(IVec2::new(x, y)) }
+
+    #[test]
+    fn test_crossing_region_edges() {
+        let pos = Position::with_tile_coords(location(3, 4), REGION_WIDTH as i32 - 1, 10);
+        let east = pos.offset(1, 0);
+        assert_eq!((east.region, east.tile.x), (location(4, 4), 0));
+        assert_eq!(east.offset(-1, 0), pos);
+
+        // The planet wraps around east/west
+        let west = Position::with_tile_coords(location(0, 4), 0, 10).offset(-1, 0);
+        assert_eq!(west.region, location(WORLD_WIDTH as i32 - 1, 4));
+        assert_eq!(west.tile.x, REGION_WIDTH as i32 - 1);
+        assert_eq!(west.chunk_min, west.chunk_location());
+    }
+
+    #[test]
+    fn test_regions_near() {
+        let center = Position::with_tile_coords(location(3, 4), 128, 128);
+        assert_eq!(regions_near(&center), vec![location(3, 4)]);
+
+        let corner = Position::with_tile_coords(location(0, 4), 10, REGION_HEIGHT as i32 - 10);
+        let mut regions = regions_near(&corner);
+        regions.sort_by_key(|r| (r.y, r.x));
+        let west = WORLD_WIDTH as i32 - 1;
+        assert_eq!(
+            regions,
+            vec![location(0, 4), location(west, 4), location(0, 5), location(west, 5)]
+        );
+
+        let pole = Position::with_tile_coords(location(3, 0), 128, 0);
+        assert_eq!(regions_near(&pole), vec![location(3, 0)]);
+    }
+}
@@ -13,6 +13,8 @@ pub enum TileType {
     Soil,
     Tree(TreeType),
     Plant(PlantType),
+    /// A slope up to the next elevation level.
+    Ramp,
 }
 
 #[bitflags]
@@ -12,14 +12,19 @@ struct DebugUIState {
 }
 
 fn display_chunk_stats(
-    chunks: Query<&Chunk>,
+    chunks: Option<Res<ChunkMap<TileType, ChunkShape>>>,
+    elevations: Option<Res<ChunkMap<u32, ChunkShape>>>,
     mut egui: ResMut<EguiContext>,
     dirty_chunks: Option<Res<DirtyChunks>>,
+    modified_chunks: Option<Res<ModifiedChunks>>,
     loaded_chunks: Option<Res<ChunkEntities>>,
     player_q: Query<&Position, With<Player>>,
     local_pos: Option<Res<CurrentLocalPlayerChunk>>,
     chunk_command_queue: Option<ResMut<ChunkCommandQueue>>,
-    chunk_loading_radius: Option<ResMut<ChunkLoadRadius>>,
+    chunk_refs: Option<Res<ChunkViewerRefs>>,
+    viewers_q: Query<(), With<ChunkViewer>>,
+    mut player_viewer_q: Query<&mut ChunkViewer, With<Player>>,
+    mut compression: Local<Option<CompressionReport>>,
 ) {
     egui::Window::new("Chunking").show(egui.ctx_mut(), |ui| {
         if let Ok(player_pos) = player_q.get_single() {
@@ -27,6 +32,12 @@ fn display_chunk_stats(
             ui.label(format!("Player region: {:?}", player_pos.region));
             ui.label(format!("Player chunk: {:?}", player_pos.chunk_min));
             ui.label(format!("Player tile position: {:?}", player_pos.tile));
+            let elevation = elevation_at(elevations.as_deref(), player_pos.to_world());
+            ui.label(format!("Player elevation: {elevation:?}"));
+            ui.label(format!("Regions: {:?}", count_regions_by_status()));
+            let region_memory =
+                REGIONS.read().regions.values().map(Region::heap_size).sum::<usize>();
+            ui.label(format!("Region memory: {} KiB", region_memory / 1024));
 
             ui.separator();
 
@@ -45,60 +56,105 @@ fn display_chunk_stats(
                 "Chunks invalidations (per frame):  {}",
                 dirty_chunks.unwrap().num_dirty()
             ));
+            if let Some(modified_chunks) = modified_chunks {
+                ui.label(format!("Unsaved chunks: {}", modified_chunks.num_modified()));
+            }
 
             ui.separator();
 
             if let Some(mut chunk_command_queue) = chunk_command_queue {
+                ui.label(format!(
+                    "Queued chunk creations: {}",
+                    chunk_command_queue.create.len()
+                ));
+
+                if let Some(max_chunks) = chunk_command_queue.budget.max_chunks.as_mut() {
+                    ui.add(egui::Slider::new(max_chunks, 1..=64).text("Chunks per frame"));
+                }
+
                 if ui.button("Clear loaded chunks").clicked() {
                     chunk_command_queue.queue_unload(loaded_chunks.iter_keys());
                 }
             }
 
-            if let Some(mut chunk_loading_radius) = chunk_loading_radius {
+            if let Some(chunk_refs) = chunk_refs {
+                ui.label(format!("Chunk viewers: {}", viewers_q.iter().count()));
+                ui.label(format!("Chunks required by viewers: {}", chunk_refs.len()));
+            }
+
+            if let Ok(mut viewer) = player_viewer_q.get_single_mut() {
                 ui.label(format!(
                     "Horizontal chunk loading radius: H: {} / V: {}",
-                    chunk_loading_radius.horizontal, chunk_loading_radius.vertical,
+                    viewer.radius.horizontal, viewer.radius.vertical,
                 ));
 
                 if ui.button("Increment").clicked() {
-                    chunk_loading_radius.horizontal += 1;
-                    chunk_loading_radius.vertical += 1;
+                    viewer.radius.horizontal += 1;
+                    viewer.radius.vertical += 1;
                 }
                 if ui.button("Decrement").clicked() {
-                    chunk_loading_radius.horizontal -= 1;
-                    chunk_loading_radius.vertical -= 1;
+                    viewer.radius.horizontal -= 1;
+                    viewer.radius.vertical -= 1;
+                }
+
+                // Only write back on change, so the view chunks aren't recomputed every frame
+                let mut shape = viewer.shape;
+                ui.horizontal(|ui| {
+                    ui.label("Shape:");
+                    ui.radio_value(&mut shape, ChunkLoadShape::Circle, "Circle");
+                    ui.radio_value(&mut shape, ChunkLoadShape::Ellipse, "Ellipse");
+                    ui.radio_value(&mut shape, ChunkLoadShape::Square, "Square");
+                    ui.radio_value(&mut shape, ChunkLoadShape::Viewport, "Viewport");
+                });
+                if shape != viewer.shape {
+                    viewer.shape = shape;
                 }
                 // ui.add(egui::Slider::new(&mut chunk_loading_radius.horizontal, 1..=32));
                 // ui.text_edit_singleline(&mut ui_state.label);
                 ui.separator();
             }
 
-            loaded_chunks.iter().for_each(|(chunk_key, chunk_entity)| {
-                let chunk = chunks.get(*chunk_entity).unwrap();
-                let floor =
-                    chunk.tiles.iter().filter(|tile| **tile == TileType::Floor).count();
-                let wall = chunk.tiles.iter().filter(|tile| **tile == TileType::Wall).count();
-                let water =
-                    chunk.tiles.iter().filter(|tile| **tile == TileType::Water).count();
-                let sand = chunk.tiles.iter().filter(|tile| **tile == TileType::Sand).count();
-                let soil = chunk.tiles.iter().filter(|tile| **tile == TileType::Soil).count();
-                let grass = chunk
-                    .tiles
-                    .iter()
-                    .filter(|tile| **tile == TileType::Plant(PlantType::Grass))
-                    .count();
-
-                let daisy = chunk
-                    .tiles
-                    .iter()
-                    .filter(|tile| **tile == TileType::Plant(PlantType::Daisy))
-                    .count();
-
-                let heather = chunk
-                    .tiles
-                    .iter()
-                    .filter(|tile| **tile == TileType::Plant(PlantType::Heather))
-                    .count();
+            let chunks = match chunks {
+                Some(chunks) => chunks,
+                None => return,
+            };
+
+            let paletted_memory: usize =
+                chunks.iter().map(|(_, buffer)| buffer.heap_size()).sum();
+            ui.label(format!(
+                "Tile memory: {paletted_memory} bytes paletted, {} bytes unpacked",
+                chunks.len() * TILES_PER_CHUNK * std::mem::size_of::<TileType>()
+            ));
+
+            // Encodes every loaded chunk, too slow to run each frame
+            if ui.button("Measure saved tiles").clicked() {
+                let tiles =
+                    chunks.iter().map(|(_, buffer)| buffer.to_vec()).collect::<Vec<_>>();
+                *compression =
+                    Some(CompressionReport::measure(tiles.iter().map(Vec::as_slice)));
+            }
+            if let Some(report) = compression.as_ref() {
+                ui.label(format!("Saved tiles: {report}"));
+            }
+            ui.separator();
+
+            loaded_chunks.iter_keys().for_each(|chunk_key| {
+                let tiles = match chunks.buffer_at(chunk_key.as_ivec2()) {
+                    Some(buffer) => buffer.to_vec(),
+                    None => return,
+                };
+
+                let count =
+                    |tile_type: TileType| tiles.iter().filter(|t| **t == tile_type).count();
+                let floor = count(TileType::Floor);
+                let wall = count(TileType::Wall);
+                let water = count(TileType::Water);
+                let sand = count(TileType::Sand);
+                let soil = count(TileType::Soil);
+                let ramp = count(TileType::Ramp);
+                let grass = count(TileType::Plant(PlantType::Grass));
+                let daisy = count(TileType::Plant(PlantType::Daisy));
+                let heather = count(TileType::Plant(PlantType::Heather));
 
                 ui.label(format!("Chunk Key {chunk_key:?}"));
                 ui.separator();
@@ -109,6 +165,7 @@ fn display_chunk_stats(
                 ui.label(format!("Chunk wall tiles {wall:?}"));
                 ui.label(format!("Chunk sand tiles {sand:?}"));
                 ui.label(format!("Chunk soil tiles {soil:?}"));
+                ui.label(format!("Chunk ramp tiles {ramp:?}"));
 
                 ui.label(format!("Chunk water tiles{water:?}"));
 
@@ -11,7 +11,21 @@ pub struct EmbarkResources {
 #[derive(Component)]
 pub struct EmbarkGrid;
 
-pub fn resume_embark_menu(mut commands: Commands, ui: Res<UiAssets>) {
+pub fn resume_embark_menu(
+    mut commands: Commands,
+    ui: Res<UiAssets>,
+    slot: Res<SaveSlot>,
+    mut errors: ResMut<ErrorDialog>,
+) {
+    let planet = match load_planet(&slot) {
+        Ok(planet) => planet,
+        Err(err) => {
+            errors.report("Failed to load world", &err);
+            commands.insert_resource(NextState(GameState::MainMenu));
+            return;
+        }
+    };
+
     commands
         .spawn_bundle(SpriteSheetBundle {
             texture_atlas: ui.backgrounds.clone(),
@@ -21,7 +35,26 @@ pub fn resume_embark_menu(mut commands: Commands, ui: Res<UiAssets>) {
         .insert(BackgroundImage {})
         .insert(EmbarkGrid {});
 
-    let planet = load_planet();
+    // Resume a game in progress instead of picking a new embark location
+    if does_game_file_exist(&slot) {
+        match load_game(&slot) {
+            Ok(game) => {
+                let pos = game.player.position;
+                game.player.spawn(&mut commands);
+
+                commands.insert_resource(CurrentLocalPlayerChunk::new(
+                    pos.chunk_location().as_ivec2(),
+                    pos.to_world(),
+                ));
+                commands.insert_resource(CameraView::new(pos.to_point()));
+                commands.insert_resource(EmbarkResources { planet, loc: pos.region.0 });
+                commands.insert_resource(NextState(GameState::RegionGen));
+                return;
+            }
+            Err(err) => println!("Error loading game, embarking again: {}", err.report()),
+        }
+    }
+
     let tiles: Vec<(TilePos, u32)> = fill_tiles(&planet);
 
     let tilemap_size = TilemapSize { x: WORLD_WIDTH as u32, y: WORLD_HEIGHT as u32 };
@@ -64,6 +97,7 @@ pub fn resume_embark_menu(mut commands: Commands, ui: Res<UiAssets>) {
 
 pub fn embark_menu(
     wnds: Res<Windows>,
+    slot: Res<SaveSlot>,
     mut commands: Commands,
     mut embark: ResMut<EmbarkResources>,
     mut egui_context: ResMut<EguiContext>,
@@ -126,22 +160,35 @@ pub fn embark_menu(
                 embark.loc = highlighed_location;
 
                 let crash_location = PlanetLocation::new(highlighed_location);
-                let tile_loc = crash_location.to_world() + IVec2 { x: 128, y: 128 };
-                let pos = Position::with_tile_coords(crash_location, tile_loc.x, tile_loc.y);
+                let pos = Position::with_tile_coords(crash_location, 128, 128);
+                let tile_loc = pos.to_world();
+
+                match load_metadata(&slot) {
+                    Ok(mut metadata) => {
+                        metadata.embark = Some(crash_location);
+                        metadata.touch();
+                        if let Err(err) = save_metadata(&slot, &metadata) {
+                            println!("Error saving world metadata: {}", err.report());
+                        }
+                    }
+                    Err(err) => println!("Error loading world metadata: {}", err.report()),
+                }
 
                 commands
                     .spawn()
                     .insert(Player)
+                    .insert(Persistent)
                     .insert(pos)
                     .insert(Glyph::new(
                         to_cp437('@'),
                         ColorPair::new(WHITE, BLACK),
                         RenderOrder::Actor,
                     ))
-                    .insert(FieldOfView::new(8));
+                    .insert(FieldOfView::new(8))
+                    .insert(ChunkViewer::default());
 
                 commands.insert_resource(CurrentLocalPlayerChunk::new(
-                    crash_location.to_world(),
+                    pos.chunk_min.as_ivec2(),
                     tile_loc,
                 ));
                 commands.insert_resource(CameraView::new(Point::new(tile_loc.x, tile_loc.y)));
@@ -21,8 +21,13 @@ pub fn embark_region_menu(
     }
 }
 
-pub fn resume_embark_region(mut commands: Commands, embark: Res<EmbarkResources>) {
-    let mut rb = RegionBuilder::new(embark.planet.clone(), PlanetLocation::new(embark.loc));
+pub fn resume_embark_region(
+    mut commands: Commands,
+    embark: Res<EmbarkResources>,
+    slot: Res<SaveSlot>,
+) {
+    let location = PlanetLocation::new(embark.loc);
+    let mut rb = RegionBuilder::new(embark.planet.clone(), location, slot.clone());
     rb.generate();
     commands.insert_resource(rb);
 }
@@ -0,0 +1,61 @@
+use crate::prelude::*;
+use bevy_egui::*;
+use std::collections::VecDeque;
+
+/// An error to show the player, with the chain of errors that caused it.
+pub struct ErrorMessage {
+    pub title: String,
+    pub report: String,
+}
+
+/// Errors waiting to be shown to the player, one dialog at a time. Systems that can't
+/// recover from an error report it here instead of panicking.
+#[derive(Default)]
+pub struct ErrorDialog {
+    errors: VecDeque<ErrorMessage>,
+}
+
+impl ErrorDialog {
+    pub fn report(&mut self, title: &str, err: &IOError) {
+        let report = err.report();
+        println!("{title}: {report}");
+
+        // The same error is often hit again before the player closes the dialog
+        if !self
+            .errors
+            .iter()
+            .any(|message| message.title == title && message.report == report)
+        {
+            self.errors.push_back(ErrorMessage { title: title.to_string(), report });
+        }
+    }
+}
+
+fn error_dialog(mut egui_context: ResMut<EguiContext>, mut dialog: ResMut<ErrorDialog>) {
+    let message = match dialog.errors.front() {
+        Some(message) => message,
+        None => return,
+    };
+
+    let mut dismissed = false;
+    egui::Window::new(&message.title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(&message.report);
+            ui.separator();
+            dismissed = ui.button("OK").clicked();
+        });
+
+    if dismissed {
+        dialog.errors.pop_front();
+    }
+}
+
+pub struct ErrorDialogPlugin;
+impl Plugin for ErrorDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ErrorDialog>().add_system(error_dialog);
+    }
+}
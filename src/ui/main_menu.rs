@@ -7,10 +7,32 @@ pub struct BackgroundImage;
 
 pub struct MainMenuState {
     tagline: String,
+    /// The most recently played world, refreshed when entering the menu.
+    last_played: Option<SaveSlot>,
+    /// The saved worlds, listed while the load world screen is open.
+    worlds: Option<Vec<WorldSummary>>,
+    /// A world waiting for the player to confirm its deletion.
+    pending_delete: Option<SaveSlot>,
 }
 
 impl Default for MainMenuState {
-    fn default() -> Self { Self { tagline: tagline() } }
+    fn default() -> Self {
+        Self { tagline: tagline(), last_played: None, worlds: None, pending_delete: None }
+    }
+}
+
+/// A saved world listed on the load world screen.
+pub struct WorldSummary {
+    slot: SaveSlot,
+    metadata: Option<WorldMetadata>,
+    size_on_disk: u64,
+    thumbnail: Option<egui::TextureHandle>,
+}
+
+enum WorldAction {
+    Play(SaveSlot),
+    Duplicate(SaveSlot),
+    Delete(SaveSlot),
 }
 
 fn get_descriptive_noun(rng: &mut RandomNumberGenerator) -> String {
@@ -45,10 +67,15 @@ fn tagline() -> String {
 
 pub fn main_menu(
     mut commands: Commands,
-    mms: Res<MainMenuState>,
+    mut mms: ResMut<MainMenuState>,
     mut egui_context: ResMut<EguiContext>,
     mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
+    ui_assets: Res<UiAssets>,
+    atlases: Res<Assets<TextureAtlas>>,
+    images: Res<Assets<Image>>,
 ) {
+    let mut open_load_world = false;
+
     egui::Window::new("Random Game?")
         .auto_sized()
         .resizable(false)
@@ -60,8 +87,15 @@ pub fn main_menu(
                 commands.insert_resource(NextState(GameState::WorldGen));
             }
 
-            if does_world_file_exist() && ui.button("Embark").clicked() {
-                commands.insert_resource(NextState(GameState::Embark));
+            if let Some(slot) = &mms.last_played {
+                if ui.button(format!("Embark on {}", slot.name())).clicked() {
+                    commands.insert_resource(slot.clone());
+                    commands.insert_resource(NextState(GameState::Embark));
+                }
+            }
+
+            if ui.button("Load World").clicked() {
+                open_load_world = true;
             }
 
             // Quit game option
@@ -70,6 +104,36 @@ pub fn main_menu(
             }
         });
 
+    if open_load_world {
+        let palette = biome_palette(&ui_assets, &atlases, &images);
+        mms.worlds = Some(scan_worlds(egui_context.ctx_mut(), &palette));
+    }
+
+    match load_world_window(egui_context.ctx_mut(), &mut mms) {
+        Some(WorldAction::Play(slot)) => {
+            mms.worlds = None;
+            commands.insert_resource(slot);
+            commands.insert_resource(NextState(GameState::Embark));
+        }
+        Some(WorldAction::Duplicate(slot)) => {
+            if let Err(err) = slot.duplicate() {
+                println!("Failed to duplicate world {}: {}", slot.name(), err.report());
+            }
+            let palette = biome_palette(&ui_assets, &atlases, &images);
+            mms.worlds = Some(scan_worlds(egui_context.ctx_mut(), &palette));
+        }
+        Some(WorldAction::Delete(slot)) => {
+            if let Err(err) = slot.delete() {
+                println!("Failed to delete world {}: {}", slot.name(), err.report());
+            }
+            if let Some(worlds) = mms.worlds.as_mut() {
+                worlds.retain(|world| world.slot != slot);
+            }
+            mms.last_played = list_save_slots().into_iter().find(does_world_file_exist);
+        }
+        None => {}
+    }
+
     egui::Window::new("Dedication").auto_sized().resizable(false).title_bar(false).show(
         egui_context.ctx_mut(),
         |ui| {
@@ -85,7 +149,186 @@ pub fn main_menu(
     );
 }
 
-pub fn resume_main_menu(mut commands: Commands, ui: Res<UiAssets>) {
+/// Shows the saved worlds while the load world screen is open, returning the action the
+/// player picked.
+fn load_world_window(ctx: &egui::Context, mms: &mut MainMenuState) -> Option<WorldAction> {
+    let mut action = None;
+    let mut delete = None;
+    let mut close = false;
+
+    let worlds = mms.worlds.as_ref()?;
+    egui::Window::new("Load World")
+        .resizable(false)
+        .fixed_pos(egui::Pos2::new(600., 100.))
+        .show(ctx, |ui| {
+            if worlds.is_empty() {
+                ui.label("No saved worlds");
+            }
+
+            egui::ScrollArea::vertical().max_height(500.).show(ui, |ui| {
+                for world in worlds.iter() {
+                    ui.horizontal(|ui| {
+                        if let Some(thumbnail) = &world.thumbnail {
+                            ui.image(thumbnail, thumbnail.size_vec2());
+                        }
+
+                        ui.vertical(|ui| {
+                            ui.heading(world.slot.name());
+                            match &world.metadata {
+                                Some(metadata) => {
+                                    ui.label(format!("Seed: {}", metadata.seed));
+                                    ui.label(format!(
+                                        "Last played: {}",
+                                        format_time_ago(metadata.last_played)
+                                    ));
+                                }
+                                None => {
+                                    ui.label("No world information");
+                                }
+                            }
+                            ui.label(format!("Size: {}", format_size(world.size_on_disk)));
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Play").clicked() {
+                                    action = Some(WorldAction::Play(world.slot.clone()));
+                                }
+                                if ui.button("Duplicate").clicked() {
+                                    action = Some(WorldAction::Duplicate(world.slot.clone()));
+                                }
+                                if ui.button("Delete").clicked() {
+                                    delete = Some(world.slot.clone());
+                                }
+                            });
+                        });
+                    });
+                    ui.separator();
+                }
+            });
+
+            if ui.button("Back").clicked() {
+                close = true;
+            }
+        });
+
+    if delete.is_some() {
+        mms.pending_delete = delete;
+    }
+
+    if let Some(slot) = mms.pending_delete.clone() {
+        egui::Window::new("Delete World").collapsible(false).resizable(false).show(
+            ctx,
+            |ui| {
+                ui.label(format!("Delete {} forever?", slot.name()));
+                ui.horizontal(|ui| {
+                    if ui.button("Delete").clicked() {
+                        action = Some(WorldAction::Delete(slot.clone()));
+                        mms.pending_delete = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        mms.pending_delete = None;
+                    }
+                });
+            },
+        );
+    }
+
+    if close {
+        mms.worlds = None;
+        mms.pending_delete = None;
+    }
+
+    action
+}
+
+/// Lists the saved worlds with a planet file, rendering a biome thumbnail for each.
+fn scan_worlds(ctx: &egui::Context, palette: &[egui::Color32]) -> Vec<WorldSummary> {
+    list_save_slots()
+        .into_iter()
+        .filter(does_world_file_exist)
+        .map(|slot| {
+            let thumbnail = load_data::<Planet>(slot.planet_path()).ok().map(|planet| {
+                let image = world_thumbnail(&planet, palette);
+                ctx.load_texture(slot.name(), image, egui::TextureFilter::Nearest)
+            });
+
+            WorldSummary {
+                metadata: load_metadata(&slot).ok(),
+                size_on_disk: slot.size_on_disk(),
+                thumbnail,
+                slot,
+            }
+        })
+        .collect()
+}
+
+/// Draws one pixel per landblock, colored like the biome's embark tile.
+fn world_thumbnail(planet: &Planet, palette: &[egui::Color32]) -> egui::ColorImage {
+    let mut image = egui::ColorImage::new([WORLD_WIDTH, WORLD_HEIGHT], egui::Color32::BLACK);
+    for (tile_pos, tile) in fill_tiles(planet) {
+        // Tilemaps grow upwards, images downwards
+        let y = WORLD_HEIGHT - 1 - tile_pos.y as usize;
+        image[(tile_pos.x as usize, y)] =
+            palette.get(tile as usize).copied().unwrap_or(egui::Color32::BLACK);
+    }
+
+    image
+}
+
+/// The color at the center of each embark tile.
+fn biome_palette(
+    ui_assets: &UiAssets,
+    atlases: &Assets<TextureAtlas>,
+    images: &Assets<Image>,
+) -> Vec<egui::Color32> {
+    let atlas = match atlases.get(&ui_assets.embark_tiles_atlas) {
+        Some(atlas) => atlas,
+        None => return Vec::new(),
+    };
+    let image = match images.get(&atlas.texture) {
+        Some(image) => image,
+        None => return Vec::new(),
+    };
+
+    let width = image.texture_descriptor.size.width as usize;
+    atlas
+        .textures
+        .iter()
+        .map(|rect| {
+            let center = (rect.min + rect.max) / 2.0;
+            let idx = (center.y as usize * width + center.x as usize) * 4;
+            match image.data.get(idx..idx + 4) {
+                Some(pixel) => egui::Color32::from_rgb(pixel[0], pixel[1], pixel[2]),
+                None => egui::Color32::BLACK,
+            }
+        })
+        .collect()
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f32 / 1024.),
+        _ => format!("{:.1} MB", bytes as f32 / 1_048_576.),
+    }
+}
+
+fn format_time_ago(timestamp: u64) -> String {
+    let seconds = unix_timestamp().saturating_sub(timestamp);
+    match seconds {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{} minutes ago", seconds / 60),
+        3600..=86399 => format!("{} hours ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+pub fn resume_main_menu(
+    mut commands: Commands,
+    ui: Res<UiAssets>,
+    mut mms: ResMut<MainMenuState>,
+) {
+    mms.last_played = list_save_slots().into_iter().find(does_world_file_exist);
+
     commands
         .spawn_bundle(SpriteSheetBundle {
             texture_atlas: ui.backgrounds.clone(),
@@ -3,12 +3,14 @@ use bevy::prelude::{Component, PluginGroup};
 mod debug;
 mod embark;
 mod embark_region;
+mod error_dialog;
 mod main_menu;
 mod world_gen;
 
 pub use debug::*;
 pub use embark::*;
 pub use embark_region::*;
+pub use error_dialog::*;
 pub use main_menu::*;
 pub use world_gen::*;
 
@@ -35,6 +37,7 @@ impl PluginGroup for UIPlugins {
             .add(WorldGenMenuPlugin)
             .add(EmbarkMenuPlugin)
             .add(EmbarkRegionPlugin)
+            .add(ErrorDialogPlugin)
             .add(DebugUiPlugin);
     }
 }
@@ -49,7 +49,10 @@ pub fn world_gen_menu(
                 }
 
                 if ui.button("Save Planet").clicked() {
-                    planet_builder.save_planet();
+                    planet_builder.save_planet(
+                        SaveSlot::unique(&res.worldgen_seed),
+                        WorldMetadata::new(&res.worldgen_seed, res.worldgen_lacunarity),
+                    );
                     commands.insert_resource(NextState(GameState::MainMenu));
                 }
             }